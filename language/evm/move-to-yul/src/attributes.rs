@@ -5,7 +5,7 @@
 
 use move_model::{
     ast::{Attribute, AttributeValue, Value},
-    model::{FunctionEnv, GlobalEnv, ModuleEnv},
+    model::{FunctionEnv, GlobalEnv, ModuleEnv, StructEnv},
 };
 
 const CREATE_ATTR: &str = "create";
@@ -15,6 +15,7 @@ const PAYABLE_ATTR: &str = "payable";
 const RECEIVE_ATTR: &str = "receive";
 const RECEIVE_FALLBACK_ATTR: &str = "fallback";
 const TEST_ATTR: &str = "evm_test";
+const INTERNAL_FIELDS_ATTR: &str = "internal_fields";
 const SIGNATURE: &str = "sig";
 
 /// Extract the value from an attribute
@@ -122,3 +123,21 @@ pub fn is_contract_fun(fun: &FunctionEnv) -> bool {
 pub fn is_test_fun(fun: &FunctionEnv<'_>) -> bool {
     has_attr(fun.module_env.env, fun.get_attributes(), TEST_ATTR, true)
 }
+
+/// Extract the field names listed in a `#[internal_fields(a, b)]` attribute of the struct.
+pub fn extract_internal_fields(st: &StructEnv<'_>) -> Vec<String> {
+    let env = st.module_env.env;
+    let mut fields = vec![];
+    for attr in st.get_attributes() {
+        if let Attribute::Apply(_, s, args) = attr {
+            if env.symbol_pool().string(*s).as_str() == INTERNAL_FIELDS_ATTR {
+                for inner_attr in args {
+                    if let Attribute::Apply(_, field, _) = inner_attr {
+                        fields.push(env.symbol_pool().string(*field).to_string());
+                    }
+                }
+            }
+        }
+    }
+    fields
+}
@@ -2,8 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    attributes, evm_transformation::EvmTransformationProcessor, native_functions::NativeFunctions,
-    yul_functions, yul_functions::YulFunction, Options,
+    attributes,
+    evm_transformation::EvmTransformationProcessor,
+    native_functions::NativeFunctions,
+    solidity_ty::{parse_value_type, SignatureParseOptions, SolidityPrimitiveType, SolidityType},
+    yul_functions,
+    yul_functions::YulFunction,
+    Options,
 };
 use codespan::FileId;
 use itertools::Itertools;
@@ -27,7 +32,7 @@ use std::{
 };
 
 /// Immutable context passed through the compilation.
-pub(crate) struct Context<'a> {
+pub struct Context<'a> {
     /// The program options.
     pub options: &'a Options,
     /// The global environment, containing the Move model.
@@ -39,14 +44,16 @@ pub(crate) struct Context<'a> {
     /// Cached memory layout info.
     pub struct_layout: RefCell<BTreeMap<QualifiedInstId<StructId>, StructLayout>>,
     /// Native function info.
-    pub native_funs: NativeFunctions,
+    pub(crate) native_funs: NativeFunctions,
     /// Mapping of file_id to file number and path.
     pub(crate) file_id_map: BTreeMap<FileId, (usize, String)>,
+    /// Options for parsing callable signatures, computed once from the target modules.
+    sig_parse_options: SignatureParseOptions,
 }
 
 /// Information about the layout of a struct in linear memory.
 #[derive(Default, Clone)]
-pub(crate) struct StructLayout {
+pub struct StructLayout {
     /// The size, in bytes, of this struct.
     pub size: usize,
     /// Offsets in linear memory and type for each field, indexed by logical offsets, i.e.
@@ -85,8 +92,10 @@ impl<'a> Context<'a> {
             writer,
             struct_layout: Default::default(),
             native_funs: NativeFunctions::default(),
+            sig_parse_options: SignatureParseOptions::default(),
         };
         ctx.native_funs = NativeFunctions::create(&ctx);
+        ctx.sig_parse_options = ctx.make_signature_parse_options();
         ctx
     }
 
@@ -276,6 +285,79 @@ impl<'a> Context<'a> {
         emitln!(self.writer, "}");
     }
 
+    /// Returns the options for parsing callable signatures.
+    pub fn signature_parse_options(&self) -> &SignatureParseOptions {
+        &self.sig_parse_options
+    }
+
+    /// Computes the options for parsing callable signatures.
+    fn make_signature_parse_options(&self) -> SignatureParseOptions {
+        let (structs, struct_field_names) = self.solidity_structs();
+        SignatureParseOptions {
+            default_reference_location: self.options.default_reference_location.clone(),
+            max_type_depth: self.options.max_type_depth,
+            structs,
+            struct_field_names,
+            value_types: self.solidity_value_types(),
+        }
+    }
+
+    /// Returns the underlying types of the user-defined value types given in the options by
+    /// name. Declarations which don't parse are skipped here; they are reported when the
+    /// options are checked.
+    fn solidity_value_types(&self) -> BTreeMap<String, SolidityPrimitiveType> {
+        self.options
+            .value_types
+            .iter()
+            .filter_map(|decl| parse_value_type(decl).ok())
+            .collect()
+    }
+
+    /// Returns the component types and the field names of the non-generic structs in target
+    /// modules, so that callable signatures can refer to them. Each struct is keyed by its
+    /// module-qualified name `M::S`, and also by its bare name `S` if no other struct in a
+    /// target module has the same name.
+    fn solidity_structs(
+        &self,
+    ) -> (
+        BTreeMap<String, Vec<SolidityType>>,
+        BTreeMap<String, Vec<String>>,
+    ) {
+        let mut structs = BTreeMap::new();
+        let mut field_names = BTreeMap::new();
+        let mut bare_name_count = BTreeMap::new();
+        for module_env in self.env.get_modules().filter(|m| m.is_target()) {
+            for struct_env in module_env.get_structs() {
+                let struct_id = struct_env.get_qualified_id();
+                if !struct_env.get_type_parameters().is_empty()
+                    || self.is_u256(struct_id)
+                    || self.is_string(struct_id)
+                {
+                    continue;
+                }
+                let ty = Type::Struct(struct_id.module_id, struct_id.id, vec![]);
+                if let Ok(SolidityType::Tuple(tys)) =
+                    SolidityType::try_translate_from_move(self, &ty)
+                {
+                    let name = self.get_struct_name(struct_id);
+                    for key in [name.clone(), self.get_qualified_struct_name(struct_id)] {
+                        field_names.insert(key.clone(), self.get_field_names(struct_id));
+                        structs.insert(key, tys.clone());
+                    }
+                    *bare_name_count.entry(name).or_insert(0) += 1;
+                }
+            }
+        }
+        // A bare name shared by structs in different modules is ambiguous.
+        for (name, count) in bare_name_count {
+            if count > 1 {
+                structs.remove(&name);
+                field_names.remove(&name);
+            }
+        }
+        (structs, field_names)
+    }
+
     /// Returns whether the struct identified by module_id and struct_id is the native U256 struct.
     pub fn is_u256(&self, struct_id: QualifiedId<StructId>) -> bool {
         let struct_env = self.env.get_struct(struct_id);
@@ -291,6 +373,28 @@ impl<'a> Context<'a> {
         ) == "String"
     }
 
+    /// Returns the name of the struct identified by module_id and struct_id, without its module.
+    pub fn get_struct_name(&self, struct_id: QualifiedId<StructId>) -> String {
+        let struct_env = self.env.get_struct(struct_id);
+        struct_env
+            .get_name()
+            .display(struct_env.symbol_pool())
+            .to_string()
+    }
+
+    /// Returns the name of the struct identified by module_id and struct_id, qualified with the
+    /// name of its module as in `M::S`.
+    pub fn get_qualified_struct_name(&self, struct_id: QualifiedId<StructId>) -> String {
+        let module_env = self.env.get_module(struct_id.module_id);
+        format!(
+            "{}::{}",
+            module_env
+                .symbol_pool()
+                .string(module_env.get_name().name()),
+            self.get_struct_name(struct_id)
+        )
+    }
+
     /// Get the field types of a struct as a vector.
     pub fn get_field_types(&self, id: QualifiedId<StructId>) -> Vec<Type> {
         self.env
@@ -300,6 +404,28 @@ impl<'a> Context<'a> {
             .collect()
     }
 
+    /// Check whether the type parameter at position `idx` of a struct is declared as phantom.
+    pub fn is_phantom_type_param(&self, id: QualifiedId<StructId>, idx: usize) -> bool {
+        self.env.get_struct(id).is_phantom_parameter(idx)
+    }
+
+    /// Get the field names of a struct as a vector.
+    pub fn get_field_names(&self, id: QualifiedId<StructId>) -> Vec<String> {
+        let struct_env = self.env.get_struct(id);
+        struct_env
+            .get_fields()
+            .map(|f| f.get_name().display(struct_env.symbol_pool()).to_string())
+            .collect()
+    }
+
+    /// Get the names of the fields of a struct which are not meant to be exposed in the ABI, as
+    /// declared with `#[internal_fields(..)]`.
+    pub fn get_internal_fields(&self, id: QualifiedId<StructId>) -> BTreeSet<String> {
+        attributes::extract_internal_fields(&self.env.get_struct(id))
+            .into_iter()
+            .collect()
+    }
+
     /// Get the layout of the instantiated struct in linear memory. The result will be cached
     /// for future calls.
     pub fn get_struct_layout(&self, st: &QualifiedInstId<StructId>) -> StructLayout {
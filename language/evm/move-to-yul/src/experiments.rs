@@ -24,4 +24,9 @@ impl Experiment {
     /// during tests this is off.
     /// Retention: permanent
     pub const CAPTURE_SOURCE_INFO: &'static str = "capture-source-info";
+
+    /// Annotate the calldata offsets of parameters in dispatch code when running a test. By
+    /// default, during tests this is off.
+    /// Retention: permanent
+    pub const CAPTURE_OFFSET_COMMENTS: &'static str = "capture-offset-comments";
 }
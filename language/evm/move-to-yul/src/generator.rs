@@ -5,7 +5,6 @@ use codespan_reporting::diagnostic::Severity;
 use std::collections::{BTreeMap, BTreeSet};
 
 use itertools::Itertools;
-use sha3::{Digest, Keccak256};
 
 use move_model::{
     ast::TempIndex,
@@ -19,8 +18,8 @@ use crate::{
     context::Context,
     functions::FunctionGenerator,
     solidity_ty::{
-        abi_head_sizes_sum, abi_head_sizes_vec, mangle_solidity_types, SignatureDataLocation,
-        SoliditySignature, SolidityType,
+        abi_head_sizes_sum, abi_head_sizes_vec, mangle_solidity_types, selector_collisions,
+        SignatureDataLocation, SoliditySignature, SolidityType, StateMutability, MAX_STACK_SLOTS,
     },
     yul_functions::{substitute_placeholders, YulFunction},
     Options,
@@ -60,7 +59,12 @@ type AuxilaryFunctionGenerator = dyn FnOnce(&mut Generator, &Context);
 impl Generator {
     /// Run the generator and produce a pair of contract name and Yul contract object.
     pub fn run(options: &Options, env: &GlobalEnv) -> (String, String) {
-        let ctx = Context::new(options, env, false);
+        Self::run_with_context(&Context::new(options, env, false))
+    }
+
+    /// Same as `run`, but uses the given context, which must not have been created for tests.
+    pub fn run_with_context(ctx: &Context) -> (String, String) {
+        let env = ctx.env;
         let mut gen = Generator::default();
         let contract_funs = ctx.get_target_functions(attributes::is_contract_fun);
         let (contract_name, contract_loc) = if contract_funs.is_empty() {
@@ -71,7 +75,7 @@ impl Generator {
             let first_module = &contract_funs[0].module_env;
             (ctx.make_contract_name(first_module), env.unknown_loc())
         };
-        gen.contract_object(&ctx, contract_loc, &contract_name, &contract_funs);
+        gen.contract_object(ctx, contract_loc, &contract_name, &contract_funs);
         (contract_name, ctx.writer.extract_result())
     }
 
@@ -352,7 +356,7 @@ impl Generator {
     fn generate_cleanup(&mut self, ty: &SolidityType) -> String {
         let name_prefix = "cleanup";
         let function_name = format!("{}_{}", name_prefix, ty);
-        let mask = ty.max_value();
+        let mask = ty.bit_mask();
 
         let generate_fun = move |_gen: &mut Generator, ctx: &Context| {
             emit!(ctx.writer, "(value) -> cleaned ");
@@ -414,6 +418,7 @@ impl Generator {
         let (ty, _) = ty_loc;
         match ty {
             Primitive(_) => self.generate_abi_decoding_primitive_type(ty),
+            UserDefined(_, p) => self.generate_abi_decoding_primitive_type(&Primitive(*p)),
             _ => "".to_string(), // TODO: non value type
         }
     }
@@ -526,6 +531,7 @@ impl Generator {
         let (ty, _) = ty_loc;
         match ty {
             Primitive(_) => self.generate_abi_encoding_primitive_type(ty),
+            UserDefined(_, p) => self.generate_abi_encoding_primitive_type(&Primitive(*p)),
             _ => "NYI".to_string(),
         }
     }
@@ -598,30 +604,22 @@ impl Generator {
         ctx: &Context,
         fun: &FunctionEnv<'_>,
         solidity_sig: &SoliditySignature,
-        selectors: &mut BTreeMap<String, QualifiedId<FunId>>,
     ) {
         let fun_id = &fun.get_qualified_id().instantiate(vec![]);
         let function_name = ctx.make_function_name(fun_id);
         let fun_sig = format!("{}", solidity_sig);
         self.need_move_function(fun_id);
-        let function_selector =
-            format!("0x{:x}", Keccak256::digest(fun_sig.as_bytes()))[..10].to_string();
-        // Check selector collision
-        if let Some(other_fun) = selectors.insert(function_selector.clone(), fun.get_qualified_id())
-        {
-            ctx.env.error(
-                &fun.get_loc(),
-                &format!(
-                    "hash collision for function selector with `{}`",
-                    ctx.env.get_function(other_fun).get_full_name_str()
-                ),
-            );
-        }
+        let function_selector = solidity_sig.selector_hex();
         emitln!(ctx.writer, "case {}", function_selector);
         ctx.emit_block(|| {
             emitln!(ctx.writer, "// {}", fun_sig);
+            if ctx.options.generate_offset_comments() {
+                for comment in solidity_sig.offset_comments() {
+                    emitln!(ctx.writer, "{}", comment);
+                }
+            }
             // TODO: check delegate call
-            if !attributes::is_payable_fun(fun) {
+            if !solidity_sig.accepts_value() {
                 self.generate_call_value_check(ctx, REVERT_ERR_NON_PAYABLE_FUN);
             }
             // Decoding
@@ -667,34 +665,32 @@ impl Generator {
     /// Generate dispatcher routine
     fn generate_dispatcher_routine(&mut self, ctx: &Context, contract_funs: &[FunctionEnv<'_>]) {
         emitln!(ctx.writer, "if iszero(lt(calldatasize(), 4))");
-        let mut selectors = BTreeMap::new();
         let para_vec = vec!["calldataload(0)".to_string(), "224".to_string()];
         let shr224 = self.call_builtin_str(ctx, YulFunction::Shr, para_vec.iter().cloned());
         ctx.emit_block(|| {
             emitln!(ctx.writer, "let selector := {}", shr224);
             emitln!(ctx.writer, "switch selector");
+            let mut dispatched = vec![];
             for fun in contract_funs {
                 if !attributes::is_callable_fun(fun) {
                     // Only dispatch callables
                     continue;
                 }
-                let extracted_sig_opt = attributes::extract_callable_signature(fun);
-                let mut sig = SoliditySignature::create_default_solidity_signature(ctx, fun);
-                if let Some(extracted_sig) = extracted_sig_opt {
-                    let parsed_sig_opt =
-                        SoliditySignature::parse_into_solidity_signature(&extracted_sig);
-                    if let Ok(parsed_sig) = parsed_sig_opt {
-                        if !parsed_sig.check_sig_compatibility(ctx, fun) {
-                            ctx.env.error(
-                                &fun.get_loc(),
-                                "solidity signature is not compatible with the move signature",
-                            );
-                        } else {
-                            sig = parsed_sig;
-                        }
-                    } else if let Err(msg) = parsed_sig_opt {
-                        ctx.env.error(&fun.get_loc(), &format!("{}", msg));
+                let mut sig = match self.callable_signature(ctx, fun) {
+                    Some(sig) => sig,
+                    None => continue,
+                };
+                if attributes::is_payable_fun(fun) {
+                    if matches!(
+                        sig.mutability,
+                        StateMutability::Pure | StateMutability::View
+                    ) {
+                        ctx.env.error(
+                            &fun.get_loc(),
+                            "a `pure` or `view` function cannot be payable",
+                        );
                     }
+                    sig.mutability = StateMutability::Payable;
                 }
                 if !self.is_suitable_for_dispatch(ctx, fun) {
                     ctx.env.diag(
@@ -704,7 +700,72 @@ impl Generator {
                     );
                     continue;
                 }
-                self.generate_dispatch_item(ctx, fun, &sig, &mut selectors);
+                if let Err(msg) = sig.check_sig_name() {
+                    ctx.env.error(&fun.get_loc(), &format!("{}", msg));
+                    continue;
+                }
+                if sig.exceeds_stack_limit() {
+                    ctx.env.diag(
+                        Severity::Warning,
+                        &fun.get_loc(),
+                        &format!(
+                            "parameters and return values of this function may need {} stack \
+                             slots, more than the {} reachable ones",
+                            sig.estimated_stack_slots(),
+                            MAX_STACK_SLOTS
+                        ),
+                    );
+                }
+                if sig.has_zero_selector() {
+                    ctx.env.diag(
+                        Severity::Warning,
+                        &fun.get_loc(),
+                        &format!(
+                            "the selector of `{}` is zero, which may be mistaken for the \
+                             fallback function",
+                            sig
+                        ),
+                    );
+                }
+                if let Some(standard) = sig.collides_with_standard() {
+                    ctx.env.diag(
+                        Severity::Warning,
+                        &fun.get_loc(),
+                        &format!(
+                            "the selector of `{}` is the same as that of the standard function \
+                             `{}`",
+                            sig, standard
+                        ),
+                    );
+                }
+                dispatched.push((fun, sig));
+            }
+            // Report functions whose selectors collide, e.g. Move functions mapped to the same
+            // overload, and only dispatch the first of them
+            let sigs = dispatched.iter().map(|(_, sig)| sig.clone()).collect_vec();
+            let mut colliding = BTreeSet::new();
+            for (first, second) in selector_collisions(&sigs) {
+                let (fun, sig) = &dispatched[second];
+                let (other_fun, other_sig) = &dispatched[first];
+                let msg = if sig.to_string() == other_sig.to_string() {
+                    format!(
+                        "signature `{}` is the same as that of `{}`",
+                        sig,
+                        other_fun.get_full_name_str()
+                    )
+                } else {
+                    format!(
+                        "hash collision for function selector with `{}`",
+                        other_fun.get_full_name_str()
+                    )
+                };
+                ctx.env.error(&fun.get_loc(), &msg);
+                colliding.insert(second);
+            }
+            for (i, (fun, sig)) in dispatched.iter().enumerate() {
+                if !colliding.contains(&i) {
+                    self.generate_dispatch_item(ctx, fun, sig);
+                }
             }
             emitln!(ctx.writer, "default {}");
         });
@@ -712,6 +773,48 @@ impl Generator {
         self.generate_fallback(ctx, receive_exists);
     }
 
+    /// Determine the signature under which a callable is dispatched, which is its explicit
+    /// signature if given, and its default one otherwise. Errors are reported for an invalid
+    /// explicit signature, in which case the default one is used to continue generating code,
+    /// and for a function without a default signature, which is not dispatched.
+    fn callable_signature(&self, ctx: &Context, fun: &FunctionEnv) -> Option<SoliditySignature> {
+        if let Some(extracted_sig) = attributes::extract_callable_signature(fun) {
+            match SoliditySignature::parse_with_options(
+                &extracted_sig,
+                ctx.signature_parse_options(),
+            ) {
+                Ok(parsed_sig) => {
+                    if let Err(msg) = parsed_sig.check_move_representable() {
+                        ctx.env.error(&fun.get_loc(), &format!("{}", msg));
+                    } else if !parsed_sig.check_sig_compatibility(ctx, fun) {
+                        ctx.env.error(
+                            &fun.get_loc(),
+                            "solidity signature is not compatible with the move signature",
+                        );
+                    } else {
+                        return Some(parsed_sig);
+                    }
+                }
+                Err(msg) => ctx.env.error(&fun.get_loc(), &format!("{}", msg)),
+            }
+            SoliditySignature::try_create_default_solidity_signature(ctx, fun).ok()
+        } else {
+            let mut warnings = vec![];
+            let default_sig =
+                SoliditySignature::try_create_default_solidity_signature_with_warnings(
+                    ctx,
+                    fun,
+                    &mut warnings,
+                );
+            for warning in warnings.into_iter().unique() {
+                ctx.env.diag(Severity::Warning, &fun.get_loc(), &warning);
+            }
+            default_sig
+                .map_err(|msg| ctx.env.error(&fun.get_loc(), &format!("{}", msg)))
+                .ok()
+        }
+    }
+
     /// Determine whether the function is suitable as a dispatcher item.
     fn is_suitable_for_dispatch(&self, ctx: &Context, fun: &FunctionEnv) -> bool {
         // TODO: once we support structs and vectors, remove check for them
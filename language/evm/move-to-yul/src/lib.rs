@@ -4,17 +4,25 @@
 #![forbid(unsafe_code)]
 
 mod attributes;
-mod context;
+pub mod context;
 mod evm_transformation;
 mod experiments;
 mod functions;
 pub mod generator;
 mod native_functions;
 pub mod options;
-mod solidity_ty;
+pub mod solidity_ty;
 mod yul_functions;
 
-use crate::{generator::Generator, options::Options};
+#[cfg(test)]
+mod unit_tests;
+
+use crate::{
+    context::Context,
+    generator::Generator,
+    options::Options,
+    solidity_ty::{module_abi_report, parse_value_type},
+};
 use anyhow::anyhow;
 use codespan_reporting::term::termcolor::{ColorChoice, StandardStream, WriteColor};
 use move_model::{
@@ -31,6 +39,10 @@ pub fn run_to_yul_errors_to_stderr(options: Options) -> anyhow::Result<()> {
 
 /// Run move-to-yul compiler and print errors to given writer.
 pub fn run_to_yul<W: WriteColor>(error_writer: &mut W, options: Options) -> anyhow::Result<()> {
+    // Check the declarations of user-defined value types.
+    for decl in &options.value_types {
+        parse_value_type(decl)?;
+    }
     // Run the model builder.
     let env = run_model_builder_with_options(
         &options.sources,
@@ -45,14 +57,22 @@ pub fn run_to_yul<W: WriteColor>(error_writer: &mut W, options: Options) -> anyh
         error_writer,
         "exiting with Move build errors",
     )?;
-    let (_, content) = Generator::run(&options, &env);
+    let ctx = Context::new(&options, &env, false);
+    // Write the ABI report before generating code, so that it is available even if some
+    // callables are rejected.
+    if let Some(path) = &options.abi_report {
+        let funs = ctx.get_target_functions(attributes::is_callable_fun);
+        let report = module_abi_report(&ctx, &funs);
+        fs::write(path, serde_json::to_string_pretty(&report)?)?;
+    }
+    let (_, content) = Generator::run_with_context(&ctx);
     check_errors(
         &env,
         &options,
         error_writer,
         "exiting with Yul generation errors",
     )?;
-    fs::write(options.output, &content)?;
+    fs::write(&options.output, &content)?;
     Ok(())
 }
 
@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::experiments::Experiment;
+use crate::{experiments::Experiment, solidity_ty::SignatureDataLocation};
 use codespan_reporting::diagnostic::Severity;
 use structopt::StructOpt;
 
@@ -28,6 +28,35 @@ pub struct Options {
     /// Whether we generate code for tests.
     #[structopt(long)]
     pub testing: bool,
+    /// Data location of reference types in callable signatures which don't specify one.
+    #[structopt(long, default_value = "memory")]
+    pub default_reference_location: SignatureDataLocation,
+    /// Whether a Solidity `string` only matches the Move string type and never `vector<u8>`.
+    #[structopt(long)]
+    pub strict_strings: bool,
+    /// Whether callables whose parameter or return types have no unique Solidity counterpart,
+    /// like `vector<u8>`, must declare an explicit signature.
+    #[structopt(long)]
+    pub require_explicit_signatures_for_ambiguous: bool,
+    /// Whether a Move address may be given as Solidity `bytes32`.
+    #[structopt(long)]
+    pub allow_address_as_bytes32: bool,
+    /// User-defined value types which callable signatures may use, given as `Name=type` where
+    /// `type` is the underlying value type, e.g. `Price=uint256`.
+    #[structopt(long = "value-type")]
+    pub value_types: Vec<String>,
+    /// Whether a numeric suffix like `_2` is dropped from the name of a Move function in its
+    /// default signature, so that Move functions can implement overloads of a Solidity function
+    /// which are told apart by their parameter types.
+    #[structopt(long)]
+    pub overload_suffixes: bool,
+    /// Maximum nesting depth of array and tuple types in callable signatures.
+    #[structopt(long, default_value = "32")]
+    pub max_type_depth: usize,
+    /// File to which a JSON report of the ABI of the callable functions is written, without
+    /// failing on incompatible signatures.
+    #[structopt(long = "abi-report")]
+    pub abi_report: Option<String>,
     /// Active experiments.
     #[structopt(short)]
     #[structopt(long = "experiment")]
@@ -62,4 +91,9 @@ impl Options {
     pub fn generate_source_info(&self) -> bool {
         !self.testing || self.experiment_on(Experiment::CAPTURE_SOURCE_INFO)
     }
+
+    /// Returns true if calldata offsets of parameters should be annotated during tests.
+    pub fn generate_offset_comments(&self) -> bool {
+        !self.testing || self.experiment_on(Experiment::CAPTURE_OFFSET_COMMENTS)
+    }
 }
@@ -8,51 +8,333 @@ use anyhow::{anyhow, Context as AnyhowContext};
 use itertools::Itertools;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::{fmt, fmt::Formatter};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sha3::Digest;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    fmt::Formatter,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
 
 use move_model::{
     model::FunctionEnv,
     ty::{PrimitiveType, Type},
 };
 
-use crate::context::Context;
+use crate::{attributes, context::Context};
 
 const PARSE_ERR_MSG: &str = "error happens when parsing the signature";
-const PARSE_ERR_MSG_SIMPLE_TYPE: &str = "error happens when parsing a simple type";
-const PARSE_ERR_MSG_ARRAY_TYPE: &str = "error happens when parsing an array type";
 const PARSE_ERR_MSG_RETURN: &str = "error happens when parsing the return types in the signature";
+const PARSE_ERR_MSG_MODIFIER_ORDER: &str = "state mutability must precede `returns`";
+const PARSE_ERR_MSG_DUPLICATE_RETURNS: &str = "only one `returns` clause is allowed";
+
+/// The maximum number of indexed parameters of a (non-anonymous) event
+pub const MAX_INDEXED_PARAMS: usize = 3;
+
+/// The number of stack slots which can be reached by the EVM's `DUP` and `SWAP` instructions
+pub const MAX_STACK_SLOTS: usize = 16;
 
 /// Represents a Solidity Signature appearing in the callable attribute.
-#[derive(Debug, Clone)]
-pub(crate) struct SoliditySignature {
+///
+/// Serialization is lossy, since types are serialized in their canonical form: a deserialized
+/// signature has the same selector, but may differ in `address payable` and the names of structs
+/// and user-defined value types, and so in its ABI JSON. The serialized form is therefore not a
+/// key for anything derived from these.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SoliditySignature {
     pub sig_name: String,
     pub para_types: Vec<(SolidityType, SignatureDataLocation)>,
     pub ret_types: Vec<(SolidityType, SignatureDataLocation)>,
+    pub mutability: StateMutability,
+    pub kind: SignatureKind,
+    /// The positions of the `indexed` parameters of an event.
+    pub indexed_params: BTreeSet<usize>,
+    /// The names of the parameters which are named in the signature, by position.
+    pub para_names: BTreeMap<usize, String>,
+    /// The names of the return values which are named in the signature, by position.
+    pub ret_names: BTreeMap<usize, String>,
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
-pub(crate) enum SignatureDataLocation {
-    // CallData, calldata is not supported yet
+/// Represents what a signature declares. Events and errors are hashed like functions, but
+/// events are identified by the full hash as their topic.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureKind {
+    Function,
+    Event,
+    Error,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureDataLocation {
+    CallData,
     Memory,
 }
 
-/// Represents a primitive value type.
+/// The default maximum nesting depth of types in a signature.
+pub const DEFAULT_MAX_TYPE_DEPTH: usize = 32;
+
+/// Options which control how a signature is parsed.
+#[derive(Debug, Clone)]
+pub struct SignatureParseOptions {
+    /// The data location of reference types for which the signature doesn't specify one. This
+    /// never changes the selector, which doesn't include data locations.
+    pub default_reference_location: SignatureDataLocation,
+    /// The maximum nesting depth of array and tuple types accepted in a signature.
+    pub max_type_depth: usize,
+    /// The component types of the structs which a signature may refer to by name.
+    pub structs: BTreeMap<String, Vec<SolidityType>>,
+    /// The field names of the structs in `structs`, which are only used for display.
+    pub struct_field_names: BTreeMap<String, Vec<String>>,
+    /// The underlying types of the user-defined value types which a signature may refer to.
+    pub value_types: BTreeMap<String, SolidityPrimitiveType>,
+}
+
+impl Default for SignatureParseOptions {
+    fn default() -> Self {
+        Self {
+            default_reference_location: SignatureDataLocation::Memory,
+            max_type_depth: DEFAULT_MAX_TYPE_DEPTH,
+            structs: BTreeMap::new(),
+            struct_field_names: BTreeMap::new(),
+            value_types: BTreeMap::new(),
+        }
+    }
+}
+
+/// Represents the state mutability of a function.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateMutability {
+    Pure,
+    View,
+    NonPayable,
+    Payable,
+}
+
+/// Represents how the decoder accesses a parameter.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum AccessKind {
+    /// Read in place with `calldataload`.
+    InPlaceCalldata,
+    /// Decoded into memory.
+    CopyToMemory,
+}
+
+/// The Solidity pragmas an interface declaring a signature needs to compile.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SolidityPragma {
+    /// The version requirement, as in `pragma solidity <version>;`.
+    pub version: &'static str,
+    /// Whether `pragma abicoder v2;` is needed.
+    pub needs_abicoder_v2: bool,
+}
+
+impl fmt::Display for SolidityPragma {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "pragma solidity {};", self.version)?;
+        if self.needs_abicoder_v2 {
+            f.write_str("\npragma abicoder v2;")?;
+        }
+        Ok(())
+    }
+}
+
+/// Represents the role of a function in an interface, as classified by a heuristic.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
-pub(crate) enum SolidityPrimitiveType {
+pub enum FunctionRole {
+    /// A `view` or `pure` function with return values.
+    Getter,
+    /// A function which changes state, has parameters and no return values.
+    Setter,
+    /// Any other function.
+    Action,
+}
+
+/// The reason why a type failed to parse, carrying the offending part of the type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SolidityTypeError {
+    /// A name which is neither a Solidity type nor a known struct.
+    UnknownType(String),
+    /// A name which is not a Solidity identifier.
+    IllegalTypeName(String),
+    /// An integer type whose width is not a multiple of 8 between 8 and 256.
+    IntRange(String),
+    /// A fixed point type whose width or number of decimal places is out of range.
+    FixedRange(String),
+    /// A static bytes type whose size is not between 1 and 32.
+    BytesRange(String),
+    /// A static array whose length is not a number.
+    BadArrayLength(String),
+    /// An array type with unbalanced brackets.
+    UnbalancedBrackets(String),
+    /// An array type with trailing content after its last bracket.
+    MalformedArray(String),
+    /// A tuple type with unbalanced parentheses or an empty component.
+    MalformedTuple(String),
+    /// A tuple type without components.
+    EmptyTuple(String),
+    /// A type whose arrays and tuples are nested deeper than the given maximum depth.
+    TooDeep(String, usize),
+}
+
+impl fmt::Display for SolidityTypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use SolidityTypeError::*;
+        match self {
+            UnknownType(s) => write!(f, "unknown type `{}`", s),
+            IllegalTypeName(s) => write!(f, "illegal type name `{}`", s),
+            IntRange(s) => write!(
+                f,
+                "the width of `{}` must be a multiple of 8 between 8 and 256",
+                s
+            ),
+            FixedRange(s) => write!(
+                f,
+                "`{}` must have a width which is a multiple of 8 between 8 and 256, and between 1 \
+                 and 80 decimal places",
+                s
+            ),
+            BytesRange(s) => write!(f, "the size of `{}` must be between 1 and 32", s),
+            BadArrayLength(s) => write!(f, "invalid array length in `{}`", s),
+            UnbalancedBrackets(s) => write!(f, "unbalanced brackets in type `{}`", s),
+            MalformedArray(s) => write!(f, "malformed array type `{}`", s),
+            MalformedTuple(s) => write!(f, "malformed tuple type `{}`", s),
+            EmptyTuple(_) => f.write_str("empty tuple is not a valid parameter type"),
+            TooDeep(s, max_depth) => write!(
+                f,
+                "type `{}` exceeds the maximum nesting depth of {}",
+                s, max_depth
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SolidityTypeError {}
+
+impl SolidityTypeError {
+    /// Returns the offending part of the type.
+    pub fn offending(&self) -> &str {
+        use SolidityTypeError::*;
+        match self {
+            UnknownType(s)
+            | IllegalTypeName(s)
+            | IntRange(s)
+            | FixedRange(s)
+            | BytesRange(s)
+            | BadArrayLength(s)
+            | UnbalancedBrackets(s)
+            | MalformedArray(s)
+            | MalformedTuple(s)
+            | EmptyTuple(s)
+            | TooDeep(s, _) => s,
+        }
+    }
+}
+
+/// The location of a parameter, or of the offending part of its type, in a signature which
+/// failed to parse. This is attached as context to the error, and `Display`s like the error.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SignatureErrorSpan {
+    /// The byte offset in the signature string.
+    pub offset: usize,
+    /// The length in bytes.
+    pub len: usize,
+    message: String,
+}
+
+impl fmt::Display for SignatureErrorSpan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// The error of parsing a single type with `FromStr`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SolidityTypeParseError {
+    /// The type string which failed to parse.
+    pub ty_str: String,
+    /// The reason of the failure.
+    pub kind: SolidityTypeError,
+}
+
+impl fmt::Display for SolidityTypeParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for SolidityTypeParseError {}
+
+/// Represents a primitive value type. Whether an integer was written as a bare alias is only
+/// kept for diagnostics and ignored by comparisons, so `uint` equals `uint256`.
+#[derive(Debug, Clone, Copy)]
+pub enum SolidityPrimitiveType {
     Bool,
-    Uint(usize),
-    Int(usize),
+    /// The width in bits, and whether the type was written as the bare alias `uint`
+    Uint(usize, bool),
+    /// The width in bits, and whether the type was written as the bare alias `int`
+    Int(usize, bool),
     Fixed(usize, usize),
     Ufixed(usize, usize),
     Address(bool),
 }
 
+impl SolidityPrimitiveType {
+    /// The parts of the type which are compared, leaving out the spelling of integer aliases
+    fn cmp_key(&self) -> (u8, usize, usize) {
+        use SolidityPrimitiveType::*;
+        match self {
+            Bool => (0, 0, 0),
+            Uint(n, _) => (1, *n, 0),
+            Int(n, _) => (2, *n, 0),
+            Fixed(m, n) => (3, *m, *n),
+            Ufixed(m, n) => (4, *m, *n),
+            Address(payable) => (5, *payable as usize, 0),
+        }
+    }
+}
+
+impl PartialEq for SolidityPrimitiveType {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp_key() == other.cmp_key()
+    }
+}
+
+impl Eq for SolidityPrimitiveType {}
+
+impl PartialOrd for SolidityPrimitiveType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SolidityPrimitiveType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_key().cmp(&other.cmp_key())
+    }
+}
+
+impl Hash for SolidityPrimitiveType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cmp_key().hash(state)
+    }
+}
+
 /// Represents a Solidity type
-/// TODO: struct
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
-pub(crate) enum SolidityType {
+pub enum SolidityType {
     Primitive(SolidityPrimitiveType),
     Tuple(Vec<SolidityType>),
+    /// A named struct with its component types, which is encoded like a tuple of them, and its
+    /// field names if they are known
+    Struct(String, Vec<SolidityType>, Vec<String>),
+    /// A user-defined value type with its name, which is encoded like its underlying type
+    UserDefined(String, SolidityPrimitiveType),
     DynamicArray(Box<SolidityType>),
     StaticArray(Box<SolidityType>, usize),
     SolidityString,
@@ -67,12 +349,63 @@ impl fmt::Display for SignatureDataLocation {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         use SignatureDataLocation::*;
         match self {
-            // CallData => f.write_str("calldata"),
+            CallData => f.write_str("calldata"),
             Memory => f.write_str("memory"),
         }
     }
 }
 
+impl FromStr for SignatureDataLocation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "calldata" => Ok(SignatureDataLocation::CallData),
+            "memory" => Ok(SignatureDataLocation::Memory),
+            _ => Err(anyhow!("unknown data location `{}`", s)),
+        }
+    }
+}
+
+// ================================================================================================
+// Pretty print for StateMutability
+
+impl fmt::Display for StateMutability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use StateMutability::*;
+        match self {
+            Pure => f.write_str("pure"),
+            View => f.write_str("view"),
+            NonPayable => f.write_str("nonpayable"),
+            Payable => f.write_str("payable"),
+        }
+    }
+}
+
+impl fmt::Display for SignatureKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use SignatureKind::*;
+        match self {
+            Function => f.write_str("function"),
+            Event => f.write_str("event"),
+            Error => f.write_str("error"),
+        }
+    }
+}
+
+impl StateMutability {
+    /// Strip a leading state mutability keyword, `nonpayable` is implicit and not a keyword.
+    fn strip_keyword(s: &str) -> Option<(Self, &str)> {
+        use StateMutability::*;
+        for mutability in [Pure, View, Payable] {
+            if let Some(rest) = strip_word(s, &mutability.to_string()) {
+                return Some((mutability, rest));
+            }
+        }
+        None
+    }
+}
+
 // ================================================================================================
 // Pretty print for SolidityPrimitiveType
 
@@ -81,8 +414,8 @@ impl fmt::Display for SolidityPrimitiveType {
         use SolidityPrimitiveType::*;
         match self {
             Bool => f.write_str("bool"),
-            Uint(n) => write!(f, "uint{}", n),
-            Int(n) => write!(f, "int{}", n),
+            Uint(n, _) => write!(f, "uint{}", n),
+            Int(n, _) => write!(f, "int{}", n),
             Fixed(m, n) => write!(f, "fixed{}x{}", m, n),
             Ufixed(m, n) => write!(f, "ufixed{}x{}", m, n),
             Address(_) => f.write_str("address"),
@@ -91,9 +424,42 @@ impl fmt::Display for SolidityPrimitiveType {
 }
 
 impl SolidityPrimitiveType {
+    /// Create `uint<bits>`, checking that the width is valid in the ABI.
+    pub fn uint(bits: usize) -> anyhow::Result<Self> {
+        SolidityPrimitiveType::Uint(bits, false).checked()
+    }
+
+    /// Create `int<bits>`, checking that the width is valid in the ABI.
+    pub fn int(bits: usize) -> anyhow::Result<Self> {
+        SolidityPrimitiveType::Int(bits, false).checked()
+    }
+
+    fn checked(self) -> anyhow::Result<Self> {
+        if self.is_valid() {
+            Ok(self)
+        } else {
+            Err(anyhow!("`{}` is not a valid ABI type", self))
+        }
+    }
+
+    /// Check whether the widths of this type are valid in the ABI. The variants can be
+    /// constructed directly, so code which depends on the width asserts this in debug builds.
+    pub fn is_valid(&self) -> bool {
+        use SolidityPrimitiveType::*;
+        match self {
+            Uint(m, _) | Int(m, _) => check_type_int_range(*m),
+            Fixed(m, n) | Ufixed(m, n) => check_type_int_range(*m) && check_fixed_n_range(*n),
+            Bool | Address(_) => true,
+        }
+    }
+
     /// Check type compatibility for primitive types
-    /// TODO: int and fixed are not supported yet
-    pub fn check_primitive_type_compatibility(
+    /// Move has no signed integers and no fixed point numbers, so `int<M>`, `fixed<M>x<N>` and
+    /// `ufixed<M>x<N>` are compatible with no Move type. Mapping `int<M>` to an unsigned type
+    /// would misrepresent the range of values.
+    /// Payability of `address` is a source-level concern which doesn't change the ABI encoding,
+    /// so `address` and `address payable` are compatible with the same Move types.
+    pub(crate) fn check_primitive_type_compatibility(
         &self,
         ctx: &Context,
         move_ty: &Type,
@@ -102,8 +468,8 @@ impl SolidityPrimitiveType {
         use SolidityPrimitiveType::*;
         match self {
             Bool => move_ty.is_bool(),
-            Uint(i) => self.check_uint_compatibility(ctx, *i, move_ty),
-            Int(i) => self.check_uint_compatibility(ctx, *i, move_ty), // current we assume int<N> in Solidity is specified in Move as a u<M> value.
+            Uint(i, _) => self.check_uint_compatibility(ctx, *i, move_ty),
+            Int(_, _) => false,
             Fixed(_, _) => false,
             Ufixed(_, _) => false,
             Address(_) => move_ty.is_signer_or_address(),
@@ -132,8 +498,8 @@ impl fmt::Display for SolidityType {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         use SolidityType::*;
         match self {
-            Primitive(ty) => write!(f, "{}", ty),
-            Tuple(tys) => {
+            Primitive(ty) | UserDefined(_, ty) => write!(f, "{}", ty),
+            Tuple(tys) | Struct(_, tys, _) => {
                 let s = tys
                     .iter()
                     .map(|ref t| format!("{}", t))
@@ -150,10 +516,69 @@ impl fmt::Display for SolidityType {
     }
 }
 
+/// Types are serialized in their canonical form, as in selectors, which drops the names of
+/// structs and user-defined value types, `address payable` and the aliases `uint` and `int`.
+/// Deserializing therefore doesn't restore these.
+impl Serialize for SolidityType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SolidityType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for SolidityPrimitiveType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SolidityPrimitiveType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ty_str = String::deserialize(deserializer)?;
+        match ty_str.parse().map_err(de::Error::custom)? {
+            SolidityType::Primitive(p) => Ok(p),
+            _ => Err(de::Error::custom(format!(
+                "`{}` is not a primitive type",
+                ty_str
+            ))),
+        }
+    }
+}
+
+impl FromStr for SolidityType {
+    type Err = SolidityTypeParseError;
+
+    /// Parse a single type, in which structs can only be given in their tuple form.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SolidityType::parse(s, &SignatureParseOptions::default()).map_err(|kind| {
+            SolidityTypeParseError {
+                ty_str: s.to_string(),
+                kind,
+            }
+        })
+    }
+}
+
 // ================================================================================================
 // Parse solidity signatures and check type compatibility
 
 impl SolidityType {
+    /// Check whether ty is an address declared as `address payable`. Payability is not part of
+    /// the canonical form printed by `Display`.
+    pub fn is_payable_address(&self) -> bool {
+        matches!(
+            self,
+            SolidityType::Primitive(SolidityPrimitiveType::Address(true))
+        )
+    }
+
     /// Check whether ty is a static type in the sense of serialization
     pub fn is_static(&self) -> bool {
         use crate::solidity_ty::SolidityType::*;
@@ -165,230 +590,776 @@ impl SolidityType {
                 .all(|t| t)
         };
         match self {
-            Primitive(_) | BytesStatic(_) => true,
-            Tuple(tys) => conjunction(tys),
+            Primitive(_) | UserDefined(_, _) | BytesStatic(_) => true,
+            Tuple(tys) | Struct(_, tys, _) => conjunction(tys),
             StaticArray(ty, _) => ty.is_static(),
             _ => false,
         }
     }
 
-    /// Check whether a type is a value type
-    fn is_value_type(&self) -> bool {
-        use crate::solidity_ty::SolidityType::*;
-        matches!(self, Primitive(_) | BytesStatic(_))
+    /// For array types, check whether the elements are dynamic, in which case they are encoded
+    /// by offsets rather than inline. Returns None for other types.
+    pub fn array_element_is_dynamic(&self) -> Option<bool> {
+        match self {
+            SolidityType::DynamicArray(ty) | SolidityType::StaticArray(ty, _) => {
+                Some(!ty.is_static())
+            }
+            _ => None,
+        }
     }
 
-    /// Returns the max value (bit mask) for a given type.
-    pub fn max_value(&self) -> String {
-        let size = self.abi_head_size(false);
-        assert!(size <= 32, "unexpected type size {} for `{}`", size, self);
-        let multipler = size * 8;
-        format!("${{MAX_U{}}}", multipler)
+    /// Returns the canonical form of this type, for comparison, in which aliases like `uint` are
+    /// no longer distinguished from their full width, `address payable` becomes `address`,
+    /// structs become tuples and user-defined value types become their underlying type. Nested
+    /// tuples are kept as they are, since collapsing them would change the selector. This is the
+    /// type obtained by parsing the `Display` form again.
+    pub fn normalize(&self) -> SolidityType {
+        use SolidityPrimitiveType::*;
+        use SolidityType::*;
+        match self {
+            Primitive(Uint(n, _)) => Primitive(Uint(*n, false)),
+            Primitive(Int(n, _)) => Primitive(Int(*n, false)),
+            Primitive(Address(_)) => Primitive(Address(false)),
+            UserDefined(_, p) => Primitive(*p).normalize(),
+            Tuple(tys) | Struct(_, tys, _) => Tuple(tys.iter().map(|ty| ty.normalize()).collect()),
+            DynamicArray(ty) => DynamicArray(Box::new(ty.normalize())),
+            StaticArray(ty, n) => StaticArray(Box::new(ty.normalize()), *n),
+            _ => self.clone(),
+        }
     }
 
-    /// Parse a move type into a solidity type
-    fn translate_from_move(ctx: &Context, ty: &Type) -> Self {
-        use PrimitiveType::*;
-        use Type::*;
-        let generate_tuple = |tys: &Vec<Type>| {
-            let s_type = tys
-                .iter()
-                .map(|t| Self::translate_from_move(ctx, t))
-                .collect::<Vec<_>>();
-            SolidityType::Tuple(s_type)
-        };
-        match ty {
-            Primitive(p) => match p {
-                Bool => SolidityType::Primitive(SolidityPrimitiveType::Bool),
-                U8 => SolidityType::Primitive(SolidityPrimitiveType::Uint(8)),
-                U64 => SolidityType::Primitive(SolidityPrimitiveType::Uint(64)),
-                U128 => SolidityType::Primitive(SolidityPrimitiveType::Uint(128)),
-                Address => SolidityType::Primitive(SolidityPrimitiveType::Address(false)),
-                Signer => SolidityType::Primitive(SolidityPrimitiveType::Address(false)),
-                Num | Range | EventStore => {
-                    panic!("unexpected field type")
-                }
-            },
-            Vector(ety) => {
-                SolidityType::DynamicArray(Box::new(Self::translate_from_move(ctx, ety)))
-            }
-            Tuple(tys) => generate_tuple(tys),
-            Struct(mid, sid, _) => {
-                if ctx.is_u256(mid.qualified(*sid)) {
-                    SolidityType::Primitive(SolidityPrimitiveType::Uint(256))
-                } else {
-                    let tys = ctx.get_field_types(mid.qualified(*sid));
-                    generate_tuple(&tys) // TODO: translate into tuple type?
-                }
-            }
-            TypeParameter(_)
-            | Reference(_, _)
-            | Fun(_, _)
-            | TypeDomain(_)
-            | ResourceDomain(_, _, _)
-            | Error
-            | Var(_) => {
-                panic!("unexpected field type")
-            }
+    /// Returns this type as it was written in the signature, keeping the aliases `uint` and
+    /// `int`, `address payable` and the names of structs and user-defined value types. Unlike
+    /// `Display`, this is not suitable for selectors.
+    pub fn to_source_string(&self) -> String {
+        use SolidityPrimitiveType::*;
+        use SolidityType::*;
+        match self {
+            Primitive(Uint(_, true)) => "uint".to_string(),
+            Primitive(Int(_, true)) => "int".to_string(),
+            Primitive(Address(true)) => "address payable".to_string(),
+            Struct(name, _, _) | UserDefined(name, _) => name.clone(),
+            Tuple(tys) => format!("({})", tys.iter().map(|ty| ty.to_source_string()).join(",")),
+            DynamicArray(ty) => format!("{}[]", ty.to_source_string()),
+            StaticArray(ty, n) => format!("{}[{}]", ty.to_source_string(), n),
+            _ => self.to_string(),
         }
     }
 
-    /// Parse a solidity type
-    /// TODO: struct is not supported yet
-    fn parse(ty_str: &str) -> anyhow::Result<Self> {
-        let trimmed_ty_str = ty_str.trim();
-        if trimmed_ty_str.contains('[') {
-            // array type
-            SolidityType::parse_array(trimmed_ty_str)
-        } else if check_simple_type_prefix(trimmed_ty_str) {
-            // primitive and byte types
-            SolidityType::parse_simple_type(trimmed_ty_str)
-        } else {
-            // Solidity identifier matching
-            static RE_GENERAL_TYPE: Lazy<Regex> =
-                Lazy::new(|| Regex::new(r"^[a-zA-Z_$][a-zA-Z_$0-9]*$").unwrap());
-            let mut error_msg = "unsupported types";
-            if !RE_GENERAL_TYPE.is_match(trimmed_ty_str) {
-                error_msg = "illegal type name";
-            }
-            // TODO: struct
-            Err(anyhow!(error_msg))
+    /// Returns this type with the field names of structs attached to their components, e.g.
+    /// `(address to, uint256 amount)`. Structs without known field names are rendered as tuples.
+    fn to_annotated_string(&self) -> String {
+        use SolidityType::*;
+        match self {
+            Struct(_, tys, names) if names.len() == tys.len() => format!(
+                "({})",
+                tys.iter()
+                    .zip(names.iter())
+                    .map(|(ty, name)| format!("{} {}", ty.to_annotated_string(), name))
+                    .join(", ")
+            ),
+            Struct(_, tys, _) | Tuple(tys) => format!(
+                "({})",
+                tys.iter().map(|ty| ty.to_annotated_string()).join(", ")
+            ),
+            DynamicArray(ty) => format!("{}[]", ty.to_annotated_string()),
+            StaticArray(ty, n) => format!("{}[{}]", ty.to_annotated_string(), n),
+            _ => self.to_string(),
         }
     }
 
-    /// Parse value, bytes and string types
-    fn parse_simple_type(ty_str: &str) -> anyhow::Result<Self> {
-        if ty_str == "bool" {
-            return Ok(SolidityType::Primitive(SolidityPrimitiveType::Bool));
-        }
-        if ty_str.starts_with("uint") {
-            let prefix_len = "uint".len();
-            if ty_str.len() > prefix_len {
-                let num = ty_str[prefix_len..]
-                    .parse::<usize>()
-                    .context(PARSE_ERR_MSG)?;
-                if check_type_int_range(num) {
-                    return Ok(SolidityType::Primitive(SolidityPrimitiveType::Uint(num)));
-                }
-            } else {
-                return Ok(SolidityType::Primitive(SolidityPrimitiveType::Uint(256)));
+    /// Check that the type could have been parsed from a signature, e.g. that integer widths
+    /// are multiples of 8 and tuples are not empty
+    fn check_abi_valid(&self) -> anyhow::Result<()> {
+        let mut valid = true;
+        self.visit(&mut |ty| {
+            valid &= match ty {
+                SolidityType::Primitive(p) | SolidityType::UserDefined(_, p) => p.is_valid(),
+                SolidityType::BytesStatic(n) => check_static_bytes_range(*n),
+                SolidityType::Tuple(tys) | SolidityType::Struct(_, tys, _) => !tys.is_empty(),
+                _ => true,
             }
+        });
+        if valid {
+            Ok(())
+        } else {
+            Err(anyhow!("`{}` is not a valid ABI type", self))
         }
-        if ty_str.starts_with("int") {
-            let prefix_len = "int".len();
-            if ty_str.len() > prefix_len {
-                let num = ty_str[prefix_len..]
-                    .parse::<usize>()
-                    .context(PARSE_ERR_MSG)?;
-                if check_type_int_range(num) {
-                    return Ok(SolidityType::Primitive(SolidityPrimitiveType::Int(num)));
+    }
+
+    /// Check whether values of this type can be represented by some Move type, which is not the
+    /// case for signed integers and fixed point numbers, also as elements of arrays or tuples.
+    pub fn is_move_representable(&self) -> bool {
+        use SolidityPrimitiveType::*;
+        let mut representable = true;
+        self.visit(&mut |ty| {
+            if let SolidityType::Primitive(p) | SolidityType::UserDefined(_, p) = ty {
+                if matches!(p, Int(_, _) | Fixed(_, _) | Ufixed(_, _)) {
+                    representable = false;
                 }
-            } else {
-                return Ok(SolidityType::Primitive(SolidityPrimitiveType::Int(256)));
             }
+        });
+        representable
+    }
+
+    /// Check whether encoding a value of this type should check that it is valid UTF-8, which is
+    /// the case for `string` since the Move value may be an arbitrary `vector<u8>`.
+    pub fn validates_utf8(&self) -> bool {
+        matches!(self, SolidityType::SolidityString)
+    }
+
+    /// Check whether a static type consists of whole words only, so that it can be encoded
+    /// word by word. With padding, as in the standard ABI, this holds for all static types.
+    /// Without padding, sub-word values like `bool` or `uint8` are not aligned.
+    pub fn is_word_aligned(&self, padded: bool) -> bool {
+        if !self.is_static() {
+            return false;
         }
-        if ty_str.starts_with("address") {
-            let prefix_len = "address".len();
-            if ty_str.len() > prefix_len {
-                let address_type_array = ty_str.split_whitespace().collect_vec();
-                if address_type_array.len() == 2 && address_type_array[1] == "payable" {
-                    return Ok(SolidityType::Primitive(SolidityPrimitiveType::Address(
-                        true,
-                    )));
+        let mut aligned = true;
+        if !padded {
+            self.visit(&mut |ty| {
+                if ty.is_value_type() {
+                    aligned &= ty.abi_head_size(false) == 32;
                 }
-            } else if ty_str == "address" {
-                return Ok(SolidityType::Primitive(SolidityPrimitiveType::Address(
-                    false,
-                )));
-            }
+            });
         }
-        if ty_str.starts_with("fixed") {
-            let prefix_len = "fixed".len();
-            if ty_str.len() > prefix_len {
-                let num_str = &ty_str[prefix_len..];
-                let x_pos = num_str.rfind('x').context(PARSE_ERR_MSG)?;
-                let num_m = num_str[0..x_pos].parse::<usize>().context(PARSE_ERR_MSG)?;
-                let num_n = num_str[x_pos + 1..]
-                    .parse::<usize>()
-                    .context(PARSE_ERR_MSG)?;
-                if check_type_int_range(num_m) && check_fixed_n_range(num_n) {
-                    return Ok(SolidityType::Primitive(SolidityPrimitiveType::Fixed(
-                        num_m, num_n,
-                    )));
-                }
-            } else {
-                return Ok(SolidityType::Primitive(SolidityPrimitiveType::Fixed(
-                    128, 18,
-                )));
-            }
+        aligned
+    }
+
+    /// Returns the smallest size of the ABI encoding of this type, excluding the offset pointing
+    /// to it if it is dynamic. Dynamic arrays, `bytes` and `string` are at least a length word.
+    fn min_encoded_size(&self) -> usize {
+        if self.is_static() {
+            return self.abi_head_size(true);
         }
-        if ty_str.starts_with("ufixed") {
-            let prefix_len = "ufixed".len();
-            if ty_str.len() > prefix_len {
-                let num_str = &ty_str[prefix_len..];
-                let x_pos = num_str.rfind('x').context(PARSE_ERR_MSG)?;
-                let num_m = num_str[0..x_pos].parse::<usize>().context(PARSE_ERR_MSG)?;
-                let num_n = num_str[x_pos + 1..]
-                    .parse::<usize>()
-                    .context(PARSE_ERR_MSG)?;
-                if check_type_int_range(num_m) && check_fixed_n_range(num_n) {
-                    return Ok(SolidityType::Primitive(SolidityPrimitiveType::Ufixed(
-                        num_m, num_n,
-                    )));
-                }
-            } else {
-                return Ok(SolidityType::Primitive(SolidityPrimitiveType::Ufixed(
-                    128, 18,
-                )));
-            }
+        match self {
+            SolidityType::StaticArray(ty, n) => n * (32 + ty.min_encoded_size()),
+            SolidityType::Tuple(tys) | SolidityType::Struct(_, tys, _) => tys
+                .iter()
+                .map(|ty| {
+                    if ty.is_static() {
+                        ty.abi_head_size(true)
+                    } else {
+                        32 + ty.min_encoded_size()
+                    }
+                })
+                .sum(),
+            _ => 32,
         }
-        if ty_str.starts_with("bytes") {
-            let prefix_len = "bytes".len();
-            if ty_str.len() > prefix_len {
-                let num = ty_str[prefix_len..]
-                    .parse::<usize>()
-                    .context(PARSE_ERR_MSG)?;
-                if check_static_bytes_range(num) {
-                    return Ok(SolidityType::BytesStatic(num));
-                }
-            } else {
-                return Ok(SolidityType::Bytes);
-            }
+    }
+
+    /// Returns the size of this type in packed mode (`abi.encodePacked`), where value types take
+    /// their natural size and elements of static arrays are still padded to words. The
+    /// components of a tuple are concatenated, as for the arguments of `abi.encodePacked`.
+    /// Returns None for types without a fixed packed size, i.e. dynamic types, which are inlined
+    /// without their length, and tuples containing them. The size of the same type in the
+    /// standard ABI is given by `abi_head_size(true)`.
+    pub fn packed_size(&self) -> Option<usize> {
+        use SolidityType::*;
+        match self {
+            Primitive(_) | UserDefined(_, _) => Some(self.abi_head_size(false)),
+            BytesStatic(n) => Some(*n),
+            StaticArray(ty, n) if ty.is_value_type() => Some(32 * n),
+            StaticArray(ty, n) => ty.packed_size().map(|size| size * n),
+            Tuple(tys) | Struct(_, tys, _) => tys.iter().map(|ty| ty.packed_size()).sum(),
+            DynamicArray(_) | SolidityString | Bytes => None,
         }
-        if ty_str == "string" {
-            return Ok(SolidityType::SolidityString);
+    }
+
+    /// Check whether the size of this type in packed mode differs from its size in the standard
+    /// ABI, so that switching between `abi.encode` and `abi.encodePacked` changes the encoding.
+    /// This holds for sub-word value types like `bool`, `uint8` or `address`, and for dynamic
+    /// types, which are inlined without offset and length in packed mode.
+    pub fn packed_differs_from_standard(&self) -> bool {
+        match self {
+            SolidityType::Tuple(tys) | SolidityType::Struct(_, tys, _) => {
+                tys.iter().any(|ty| ty.packed_differs_from_standard())
+            }
+            _ => self.packed_size() != Some(self.abi_head_size(true)),
         }
-        Err(anyhow!(PARSE_ERR_MSG_SIMPLE_TYPE))
     }
 
-    /// Parse array types
-    fn parse_array(ty_str: &str) -> anyhow::Result<Self> {
-        let last_pos = ty_str.rfind('[').context(PARSE_ERR_MSG)?;
-        let out_type = SolidityType::parse(&ty_str[..last_pos])?;
-        let last_indice_str = &ty_str[last_pos..].trim();
-        if last_indice_str.len() >= 2
-            && last_indice_str.starts_with('[')
-            && last_indice_str.ends_with(']')
-        {
-            let length_opt = last_indice_str[1..last_indice_str.len() - 1].trim();
-            if !length_opt.is_empty() {
-                return Ok(SolidityType::StaticArray(
-                    Box::new(out_type),
-                    length_opt.parse::<usize>().context(PARSE_ERR_MSG)?,
-                ));
-            } else {
-                return Ok(SolidityType::DynamicArray(Box::new(out_type)));
+    /// Returns the offset and size of each component of a tuple in packed mode, with the
+    /// components concatenated without padding. A type other than a tuple is laid out as the
+    /// only component. The layout ends before the first component without a fixed packed size,
+    /// since the offsets of all later components depend on its length.
+    pub fn packed_layout(&self) -> Vec<(usize, usize)> {
+        let tys = match self {
+            SolidityType::Tuple(tys) | SolidityType::Struct(_, tys, _) => tys.as_slice(),
+            ty => std::slice::from_ref(ty),
+        };
+        let mut offset = 0;
+        let mut layout = vec![];
+        for ty in tys {
+            match ty.packed_size() {
+                Some(size) => {
+                    layout.push((offset, size));
+                    offset += size;
+                }
+                None => break,
             }
         }
-        Err(anyhow!(PARSE_ERR_MSG_ARRAY_TYPE))
+        layout
     }
 
-    /// Compute the data size of ty on the stack
-    pub fn abi_head_size(&self, padded: bool) -> usize {
+    /// Check whether a value of this type may be stored at the given location. As in Solidity,
+    /// `calldata` is only legal for reference types.
+    pub fn check_location_compatibility(&self, location: &SignatureDataLocation) -> bool {
+        *location != SignatureDataLocation::CallData || !self.is_value_type()
+    }
+
+    /// Returns how the decoder accesses a parameter of this type at the given location. Static
+    /// types in calldata are read in place, while dynamic types are still copied to memory so
+    /// that they can be iterated.
+    pub fn access_kind(&self, location: &SignatureDataLocation) -> AccessKind {
+        if *location == SignatureDataLocation::CallData && self.is_static() {
+            AccessKind::InPlaceCalldata
+        } else {
+            AccessKind::CopyToMemory
+        }
+    }
+
+    /// Check whether a type is a value type
+    fn is_value_type(&self) -> bool {
+        use crate::solidity_ty::SolidityType::*;
+        matches!(self, Primitive(_) | UserDefined(_, _) | BytesStatic(_))
+    }
+
+    /// Returns the bit mask for a given type, independent of its signedness.
+    pub fn bit_mask(&self) -> String {
+        let size = self.abi_head_size(false);
+        assert!(size <= 32, "unexpected type size {} for `{}`", size, self);
+        let multipler = size * 8;
+        format!("${{MAX_U{}}}", multipler)
+    }
+
+    /// Returns the max value for a given type, to be used in range checks. This is
+    /// `2^(n-1)-1` for `int<n>` and the bit mask of the type otherwise.
+    pub fn max_value(&self) -> String {
+        if let SolidityType::Primitive(p) | SolidityType::UserDefined(_, p) = self {
+            debug_assert!(p.is_valid(), "invalid primitive type `{}`", p);
+        }
+        if let SolidityType::Primitive(SolidityPrimitiveType::Int(size, _))
+        | SolidityType::UserDefined(_, SolidityPrimitiveType::Int(size, _)) = self
+        {
+            format!("${{MAX_I{}}}", size)
+        } else {
+            self.bit_mask()
+        }
+    }
+
+    /// Returns the big-endian 32-byte word holding the max value of a value type, or None for
+    /// other types. Like their ABI encoding, `bytes<n>` are left-aligned.
+    pub fn max_value_bytes(&self) -> Option<Vec<u8>> {
+        use SolidityPrimitiveType::*;
+        let mut word = vec![0u8; 32];
+        match self {
+            SolidityType::Primitive(p) | SolidityType::UserDefined(_, p) => {
+                let (bits, signed) = match p {
+                    Bool => {
+                        word[31] = 1;
+                        return Some(word);
+                    }
+                    Uint(n, _) | Ufixed(n, _) => (*n, false),
+                    Int(n, _) | Fixed(n, _) => (*n, true),
+                    Address(_) => (160, false),
+                };
+                let start = 32 - bits / 8;
+                for b in &mut word[start..] {
+                    *b = 0xff;
+                }
+                if signed {
+                    word[start] = 0x7f;
+                }
+            }
+            SolidityType::BytesStatic(n) => {
+                for b in &mut word[..*n] {
+                    *b = 0xff;
+                }
+            }
+            _ => return None,
+        }
+        Some(word)
+    }
+
+    /// Pad the big-endian bytes of a value of a value type to a 32-byte word, following the ABI:
+    /// `bytes<n>` is left-aligned and all other value types are right-aligned.
+    pub fn pad_to_word(&self, raw: &[u8]) -> anyhow::Result<[u8; 32]> {
+        use SolidityPrimitiveType::*;
+        let (size, left_aligned) = match self {
+            SolidityType::Primitive(p) | SolidityType::UserDefined(_, p) => match p {
+                Bool => (1, false),
+                Uint(n, _) | Int(n, _) | Fixed(n, _) | Ufixed(n, _) => (n / 8, false),
+                Address(_) => (20, false),
+            },
+            SolidityType::BytesStatic(n) => (*n, true),
+            _ => return Err(anyhow!("`{}` is not a value type", self)),
+        };
+        if raw.len() > size {
+            return Err(anyhow!(
+                "{} bytes exceed the size of `{}`, which is {} bytes",
+                raw.len(),
+                self,
+                size
+            ));
+        }
+        let mut word = [0u8; 32];
+        if left_aligned {
+            word[..raw.len()].copy_from_slice(raw);
+        } else {
+            word[32 - raw.len()..].copy_from_slice(raw);
+        }
+        Ok(word)
+    }
+
+    /// Returns the min value for a given type, to be used in range checks. This is `-2^(n-1)`
+    /// in two's complement for `int<n>` and 0 otherwise.
+    pub fn min_value(&self) -> String {
+        if let SolidityType::Primitive(SolidityPrimitiveType::Int(size, _))
+        | SolidityType::UserDefined(_, SolidityPrimitiveType::Int(size, _)) = self
+        {
+            format!("${{MIN_I{}}}", size)
+        } else {
+            "0".to_string()
+        }
+    }
+
+    /// Call `f` on this type and, recursively, on all its component types
+    pub fn visit(&self, f: &mut impl FnMut(&SolidityType)) {
+        use SolidityType::*;
+        f(self);
+        match self {
+            Tuple(tys) | Struct(_, tys, _) => {
+                for ty in tys {
+                    ty.visit(f);
+                }
+            }
+            DynamicArray(ty) | StaticArray(ty, _) => ty.visit(f),
+            Primitive(_) | UserDefined(_, _) | SolidityString | Bytes | BytesStatic(_) => {}
+        }
+    }
+
+    /// Returns the nesting depth of array and tuple types, which is zero for all other types.
+    pub fn depth(&self) -> usize {
+        use SolidityType::*;
+        match self {
+            Tuple(tys) | Struct(_, tys, _) => {
+                1 + tys.iter().map(|ty| ty.depth()).max().unwrap_or(0)
+            }
+            DynamicArray(ty) | StaticArray(ty, _) => 1 + ty.depth(),
+            Primitive(_) | UserDefined(_, _) | SolidityString | Bytes | BytesStatic(_) => 0,
+        }
+    }
+
+    /// Returns the name of a named type, or None for anonymous and primitive types.
+    fn type_name(&self) -> Option<String> {
+        match self {
+            SolidityType::Struct(name, _, _) | SolidityType::UserDefined(name, _) => {
+                Some(name.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns an approximation of the Rust type which bindings, like those of ethers-rs, use
+    /// for this type. Integers without a native Rust counterpart become `U256` or `I256`.
+    pub fn rust_type(&self) -> String {
+        use SolidityPrimitiveType::*;
+        match self {
+            SolidityType::Primitive(p) | SolidityType::UserDefined(_, p) => match p {
+                Bool => "bool".to_string(),
+                Uint(n, _) if matches!(n, 8 | 16 | 32 | 64 | 128) => format!("u{}", n),
+                Int(n, _) if matches!(n, 8 | 16 | 32 | 64 | 128) => format!("i{}", n),
+                Uint(_, _) | Ufixed(_, _) => "U256".to_string(),
+                Int(_, _) | Fixed(_, _) => "I256".to_string(),
+                Address(_) => "Address".to_string(),
+            },
+            SolidityType::Tuple(tys) | SolidityType::Struct(_, tys, _) => {
+                rust_tuple_type(tys.iter())
+            }
+            SolidityType::DynamicArray(ty) => format!("Vec<{}>", ty.rust_type()),
+            SolidityType::StaticArray(ty, n) => format!("[{}; {}]", ty.rust_type(), n),
+            SolidityType::SolidityString => "String".to_string(),
+            SolidityType::Bytes => "Vec<u8>".to_string(),
+            SolidityType::BytesStatic(n) => format!("[u8; {}]", n),
+        }
+    }
+
+    /// Parse a move type into a solidity type, failing for types which have no counterpart
+    pub(crate) fn try_translate_from_move(ctx: &Context, ty: &Type) -> anyhow::Result<Self> {
+        Self::try_translate_from_move_with_warnings(ctx, ty, &mut vec![])
+    }
+
+    /// Parse a move type into a solidity type like `try_translate_from_move`, accumulating
+    /// warnings about fields of structs which are exposed by flattening them into tuples.
+    pub(crate) fn try_translate_from_move_with_warnings(
+        ctx: &Context,
+        ty: &Type,
+        warnings: &mut Vec<String>,
+    ) -> anyhow::Result<Self> {
+        use PrimitiveType::*;
+        use Type::*;
+        let mut generate_tuple = |tys: &Vec<Type>| -> anyhow::Result<Self> {
+            let s_type = tys
+                .iter()
+                .map(|t| Self::try_translate_from_move_with_warnings(ctx, t, warnings))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(SolidityType::Tuple(s_type))
+        };
+        let spec_type_err = |name: &str| {
+            anyhow!(
+                "internal spec type `{}` cannot be exposed in the ABI; this usually indicates \
+                 a specification expression leaked into a callable",
+                name
+            )
+        };
+        let type_param_err = || {
+            anyhow!(
+                "unsubstituted type parameter in ABI type; generic functions must be \
+                 instantiated before ABI export"
+            )
+        };
+        Ok(match ty {
+            Primitive(p) => match p {
+                Bool => SolidityType::Primitive(SolidityPrimitiveType::Bool),
+                U8 => SolidityType::Primitive(SolidityPrimitiveType::Uint(8, false)),
+                U64 => SolidityType::Primitive(SolidityPrimitiveType::Uint(64, false)),
+                U128 => SolidityType::Primitive(SolidityPrimitiveType::Uint(128, false)),
+                Address => SolidityType::Primitive(SolidityPrimitiveType::Address(false)),
+                Signer => SolidityType::Primitive(SolidityPrimitiveType::Address(false)),
+                Num => return Err(spec_type_err("Num")),
+                Range => return Err(spec_type_err("Range")),
+                EventStore => return Err(spec_type_err("EventStore")),
+            },
+            Vector(ety) => SolidityType::DynamicArray(Box::new(
+                Self::try_translate_from_move_with_warnings(ctx, ety, warnings)?,
+            )),
+            Tuple(tys) => generate_tuple(tys)?,
+            Struct(mid, sid, inst) => {
+                let struct_id = mid.qualified(*sid);
+                if ctx.is_u256(struct_id) {
+                    SolidityType::Primitive(SolidityPrimitiveType::Uint(256, false))
+                } else {
+                    // Phantom type parameters don't occur in the fields and may stay generic
+                    if inst
+                        .iter()
+                        .enumerate()
+                        .any(|(idx, ty)| ty.is_open() && !ctx.is_phantom_type_param(struct_id, idx))
+                    {
+                        return Err(type_param_err());
+                    }
+                    let tys = ctx
+                        .get_field_types(struct_id)
+                        .iter()
+                        .map(|ty| ty.instantiate(inst))
+                        .collect_vec();
+                    let tuple = generate_tuple(&tys)?; // TODO: translate into tuple type?
+                    warnings.extend(exposed_field_warnings(
+                        &ctx.get_struct_name(struct_id),
+                        &ctx.get_field_names(struct_id),
+                        &ctx.get_internal_fields(struct_id),
+                    ));
+                    tuple
+                }
+            }
+            TypeParameter(_) => return Err(type_param_err()),
+            Reference(_, bt) => Self::try_translate_from_move_with_warnings(ctx, bt, warnings)?,
+            Fun(_, _) | TypeDomain(_) | ResourceDomain(_, _, _) | Error | Var(_) => {
+                return Err(anyhow!("unexpected field type"))
+            }
+        })
+    }
+
+    /// Suggest a move type which is compatible with this type. Returns None if no such type
+    /// can be given without resolving a struct, as for `uint256`, `string` in strict mode and
+    /// tuples, or if there is none, as for signed integers and fixed point numbers.
+    pub(crate) fn suggest_move_type(&self, ctx: &Context) -> Option<Type> {
+        use SolidityPrimitiveType::*;
+        let byte_vector = || Type::Vector(Box::new(Type::Primitive(PrimitiveType::U8)));
+        match self {
+            SolidityType::Primitive(p) | SolidityType::UserDefined(_, p) => match p {
+                Bool => Some(Type::Primitive(PrimitiveType::Bool)),
+                Uint(n, _) => match n {
+                    8 => Some(Type::Primitive(PrimitiveType::U8)),
+                    n if *n <= 64 => Some(Type::Primitive(PrimitiveType::U64)),
+                    n if *n <= 128 => Some(Type::Primitive(PrimitiveType::U128)),
+                    _ => None,
+                },
+                Address(_) => Some(Type::Primitive(PrimitiveType::Address)),
+                Int(_, _) | Fixed(_, _) | Ufixed(_, _) => None,
+            },
+            SolidityType::DynamicArray(ty) | SolidityType::StaticArray(ty, _) => ty
+                .suggest_move_type(ctx)
+                .map(|ety| Type::Vector(Box::new(ety))),
+            SolidityType::SolidityString if ctx.options.strict_strings => None,
+            SolidityType::SolidityString | SolidityType::Bytes | SolidityType::BytesStatic(_) => {
+                Some(byte_vector())
+            }
+            SolidityType::Tuple(_) | SolidityType::Struct(_, _, _) => None,
+        }
+    }
+
+    /// Check whether the default translation of a move type is ambiguous, i.e. `vector<u8>`,
+    /// which may be either `bytes` or `uint8[]`.
+    fn has_ambiguous_translation(ctx: &Context, ty: &Type) -> bool {
+        match ty {
+            Type::Vector(ety) => {
+                matches!(**ety, Type::Primitive(PrimitiveType::U8))
+                    || Self::has_ambiguous_translation(ctx, ety)
+            }
+            Type::Tuple(tys) => tys.iter().any(|t| Self::has_ambiguous_translation(ctx, t)),
+            Type::Struct(mid, sid, _) if !ctx.is_u256(mid.qualified(*sid)) => ctx
+                .get_field_types(mid.qualified(*sid))
+                .iter()
+                .any(|t| Self::has_ambiguous_translation(ctx, t)),
+            _ => false,
+        }
+    }
+
+    /// Check that a move type may be translated by default. If explicit signatures are required
+    /// for ambiguous types, this fails for those.
+    pub(crate) fn check_default_translation(ctx: &Context, ty: &Type) -> anyhow::Result<()> {
+        if ctx.options.require_explicit_signatures_for_ambiguous
+            && Self::has_ambiguous_translation(ctx, ty)
+        {
+            Err(anyhow!(
+                "`vector<u8>` may be either `bytes` or `uint8[]` in Solidity, \
+                 an explicit signature is required"
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Parse a solidity type. Structs, also as array elements, are given either in their tuple
+    /// form or by a name defined in `options.structs`. Types nested deeper than
+    /// `options.max_type_depth` are rejected while parsing, before the recursion gets deeper.
+    fn parse(ty_str: &str, options: &SignatureParseOptions) -> Result<Self, SolidityTypeError> {
+        SolidityType::parse_nested(ty_str, options, 0).map_err(|e| match e {
+            // Report the whole type instead of its innermost part which is too deep
+            SolidityTypeError::TooDeep(_, max_depth) => {
+                SolidityTypeError::TooDeep(ty_str.trim().to_string(), max_depth)
+            }
+            e => e,
+        })
+    }
+
+    /// Parse a solidity type which is nested in `depth` arrays and tuples
+    fn parse_nested(
+        ty_str: &str,
+        options: &SignatureParseOptions,
+        depth: usize,
+    ) -> Result<Self, SolidityTypeError> {
+        let trimmed_ty_str = ty_str.trim();
+        let too_deep =
+            || SolidityTypeError::TooDeep(trimmed_ty_str.to_string(), options.max_type_depth);
+        if trimmed_ty_str.starts_with('(') && trimmed_ty_str.ends_with(')') {
+            // tuple type
+            if depth >= options.max_type_depth {
+                return Err(too_deep());
+            }
+            SolidityType::parse_tuple(trimmed_ty_str, options, depth + 1)
+        } else if trimmed_ty_str.contains(['[', ']']) {
+            // array type
+            if depth >= options.max_type_depth {
+                return Err(too_deep());
+            }
+            SolidityType::parse_array(trimmed_ty_str, options, depth + 1)
+        } else if let Some(tys) = options.structs.get(trimmed_ty_str) {
+            // named struct type
+            let ty = SolidityType::Struct(
+                trimmed_ty_str.to_string(),
+                tys.clone(),
+                options
+                    .struct_field_names
+                    .get(trimmed_ty_str)
+                    .cloned()
+                    .unwrap_or_default(),
+            );
+            if depth + ty.depth() > options.max_type_depth {
+                return Err(too_deep());
+            }
+            Ok(ty)
+        } else if let Some(p) = options.value_types.get(trimmed_ty_str) {
+            // user-defined value type
+            Ok(SolidityType::UserDefined(trimmed_ty_str.to_string(), *p))
+        } else if check_simple_type_prefix(trimmed_ty_str) {
+            // primitive and byte types
+            SolidityType::parse_simple_type(trimmed_ty_str)
+        } else {
+            // Solidity identifier matching
+            static RE_GENERAL_TYPE: Lazy<Regex> =
+                Lazy::new(|| Regex::new(r"^[a-zA-Z_$][a-zA-Z_$0-9]*$").unwrap());
+            if RE_GENERAL_TYPE.is_match(trimmed_ty_str) {
+                Err(SolidityTypeError::UnknownType(trimmed_ty_str.to_string()))
+            } else {
+                Err(SolidityTypeError::IllegalTypeName(
+                    trimmed_ty_str.to_string(),
+                ))
+            }
+        }
+    }
+
+    /// Parse value, bytes and string types
+    fn parse_simple_type(ty_str: &str) -> Result<Self, SolidityTypeError> {
+        let unknown = || SolidityTypeError::UnknownType(ty_str.to_string());
+        // Parse the number following `prefix`, where `None` stands for the bare prefix
+        let parse_suffix = |prefix: &str| -> Result<Option<usize>, SolidityTypeError> {
+            let suffix = &ty_str[prefix.len()..];
+            if suffix.is_empty() {
+                Ok(None)
+            } else {
+                suffix.parse::<usize>().map(Some).map_err(|_| unknown())
+            }
+        };
+        // Parse the `<M>x<N>` following `prefix` of a fixed point type
+        let parse_fixed = |prefix: &str| -> Result<Option<(usize, usize)>, SolidityTypeError> {
+            let num_str = &ty_str[prefix.len()..];
+            if num_str.is_empty() {
+                return Ok(None);
+            }
+            let x_pos = num_str.rfind('x').ok_or_else(unknown)?;
+            let num_m = num_str[0..x_pos].parse::<usize>().map_err(|_| unknown())?;
+            let num_n = num_str[x_pos + 1..]
+                .parse::<usize>()
+                .map_err(|_| unknown())?;
+            if check_type_int_range(num_m) && check_fixed_n_range(num_n) {
+                Ok(Some((num_m, num_n)))
+            } else {
+                Err(SolidityTypeError::FixedRange(ty_str.to_string()))
+            }
+        };
+        if ty_str == "bool" {
+            return Ok(SolidityType::Primitive(SolidityPrimitiveType::Bool));
+        }
+        if ty_str.starts_with("uint") {
+            return match parse_suffix("uint")? {
+                Some(num) => SolidityPrimitiveType::uint(num)
+                    .map(SolidityType::Primitive)
+                    .map_err(|_| SolidityTypeError::IntRange(ty_str.to_string())),
+                None => Ok(SolidityType::Primitive(SolidityPrimitiveType::Uint(
+                    256, true,
+                ))),
+            };
+        }
+        if ty_str.starts_with("int") {
+            return match parse_suffix("int")? {
+                Some(num) => SolidityPrimitiveType::int(num)
+                    .map(SolidityType::Primitive)
+                    .map_err(|_| SolidityTypeError::IntRange(ty_str.to_string())),
+                None => Ok(SolidityType::Primitive(SolidityPrimitiveType::Int(
+                    256, true,
+                ))),
+            };
+        }
+        if ty_str.starts_with("address") {
+            let address_type_array = ty_str.split_whitespace().collect_vec();
+            if ty_str == "address" {
+                return Ok(SolidityType::Primitive(SolidityPrimitiveType::Address(
+                    false,
+                )));
+            } else if address_type_array.len() == 2
+                && address_type_array[0] == "address"
+                && address_type_array[1] == "payable"
+            {
+                return Ok(SolidityType::Primitive(SolidityPrimitiveType::Address(
+                    true,
+                )));
+            }
+            return Err(unknown());
+        }
+        if ty_str.starts_with("fixed") {
+            let (num_m, num_n) = parse_fixed("fixed")?.unwrap_or((128, 18));
+            return Ok(SolidityType::Primitive(SolidityPrimitiveType::Fixed(
+                num_m, num_n,
+            )));
+        }
+        if ty_str.starts_with("ufixed") {
+            let (num_m, num_n) = parse_fixed("ufixed")?.unwrap_or((128, 18));
+            return Ok(SolidityType::Primitive(SolidityPrimitiveType::Ufixed(
+                num_m, num_n,
+            )));
+        }
+        if ty_str.starts_with("bytes") {
+            return match parse_suffix("bytes")? {
+                Some(num) if check_static_bytes_range(num) => Ok(SolidityType::BytesStatic(num)),
+                Some(_) => Err(SolidityTypeError::BytesRange(ty_str.to_string())),
+                None => Ok(SolidityType::Bytes),
+            };
+        }
+        // `byte` is the deprecated alias of `bytes1`
+        if ty_str == "byte" {
+            return Ok(SolidityType::BytesStatic(1));
+        }
+        if ty_str == "string" {
+            return Ok(SolidityType::SolidityString);
+        }
+        Err(unknown())
+    }
+
+    /// Parse array types, whose elements are nested in `depth` arrays and tuples
+    fn parse_array(
+        ty_str: &str,
+        options: &SignatureParseOptions,
+        depth: usize,
+    ) -> Result<Self, SolidityTypeError> {
+        if !check_array_brackets(ty_str) {
+            return Err(SolidityTypeError::UnbalancedBrackets(ty_str.to_string()));
+        }
+        let last_pos = ty_str
+            .rfind('[')
+            .ok_or_else(|| SolidityTypeError::UnbalancedBrackets(ty_str.to_string()))?;
+        let out_type = SolidityType::parse_nested(&ty_str[..last_pos], options, depth)?;
+        let last_indice_str = &ty_str[last_pos..].trim();
+        if last_indice_str.len() >= 2
+            && last_indice_str.starts_with('[')
+            && last_indice_str.ends_with(']')
+        {
+            let length_opt = last_indice_str[1..last_indice_str.len() - 1].trim();
+            if !length_opt.is_empty() {
+                let length = length_opt
+                    .parse::<usize>()
+                    .map_err(|_| SolidityTypeError::BadArrayLength(ty_str.to_string()))?;
+                return Ok(SolidityType::StaticArray(Box::new(out_type), length));
+            } else {
+                return Ok(SolidityType::DynamicArray(Box::new(out_type)));
+            }
+        }
+        Err(SolidityTypeError::MalformedArray(ty_str.to_string()))
+    }
+
+    /// Parse tuple types, whose components are nested in `depth` arrays and tuples
+    fn parse_tuple(
+        ty_str: &str,
+        options: &SignatureParseOptions,
+        depth: usize,
+    ) -> Result<Self, SolidityTypeError> {
+        let malformed = || SolidityTypeError::MalformedTuple(ty_str.to_string());
+        if find_matching_paren(ty_str, 0) != Some(ty_str.len() - 1) {
+            return Err(malformed());
+        }
+        let components =
+            split_top_level_commas(&ty_str[1..ty_str.len() - 1]).map_err(|_| malformed())?;
+        if components.len() == 1 && components[0].trim().is_empty() {
+            // An empty tuple has no ABI encoding, unlike an empty return list
+            return Err(SolidityTypeError::EmptyTuple(ty_str.to_string()));
+        }
+        let mut tys = vec![];
+        for component in components {
+            if component.trim().is_empty() {
+                return Err(malformed());
+            }
+            tys.push(SolidityType::parse_nested(component, options, depth)?);
+        }
+        Ok(SolidityType::Tuple(tys))
+    }
+
+    /// Compute the data size of ty on the stack
+    pub fn abi_head_size(&self, padded: bool) -> usize {
         use crate::solidity_ty::{SolidityPrimitiveType::*, SolidityType::*};
         if self.is_static() {
             match self {
-                Primitive(p) => match p {
+                Primitive(p) | UserDefined(_, p) => match p {
                     Bool => {
                         if padded {
                             32
@@ -396,7 +1367,8 @@ impl SolidityType {
                             1
                         }
                     }
-                    Int(size) | Uint(size) | Fixed(size, _) | Ufixed(size, _) => {
+                    Int(size, _) | Uint(size, _) | Fixed(size, _) | Ufixed(size, _) => {
+                        debug_assert!(p.is_valid(), "invalid primitive type `{}`", p);
                         if padded {
                             32
                         } else {
@@ -422,10 +1394,10 @@ impl SolidityType {
                     if padded {
                         32
                     } else {
-                        size * 8
+                        *size
                     }
                 }
-                Tuple(tys) => abi_head_sizes_sum(tys, padded),
+                Tuple(tys) | Struct(_, tys, _) => abi_head_sizes_sum(tys, padded),
                 _ => panic!("unexpected field type"),
             }
         } else {
@@ -434,11 +1406,50 @@ impl SolidityType {
         }
     }
 
-    /// Check whether a solidity type is compatible with its corresponding move type
-    /// TODO: int<M>, fixed, struct are not supported yets
-    fn check_type_compatibility(&self, ctx: &Context, move_ty: &Type) -> bool {
+    /// Compute the size of the tail of a value of this type, which follows the heads of the
+    /// enclosing tuple. For `bytes` and `string`, `elem_count` is the number of bytes and the
+    /// tail is a length word followed by the bytes padded to whole words. For a dynamic array,
+    /// `elem_count` is the number of elements and the tail is a length word followed by the
+    /// heads of the elements. The tails of dynamic elements are not included and need to be
+    /// summed per element. Other dynamic types have no length and their tail consists of the
+    /// heads of their components, and static types have no tail.
+    pub fn tail_size_for_len(&self, elem_count: usize) -> usize {
+        if self.is_static() {
+            return 0;
+        }
         match self {
-            SolidityType::Primitive(p) => p.check_primitive_type_compatibility(ctx, move_ty),
+            SolidityType::Bytes | SolidityType::SolidityString => 32 + (elem_count + 31) / 32 * 32,
+            SolidityType::DynamicArray(ety) => 32 + elem_count * ety.abi_head_size(true),
+            SolidityType::StaticArray(ety, n) => n * ety.abi_head_size(true),
+            SolidityType::Tuple(tys) | SolidityType::Struct(_, tys, _) => {
+                abi_head_sizes_sum(tys, true)
+            }
+            _ => 0,
+        }
+    }
+
+    /// The number of stack slots needed to hold a value of this type: one per word for static
+    /// types and one for the memory pointer of dynamic types.
+    fn stack_slots(&self) -> usize {
+        if self.is_static() {
+            self.abi_head_size(true) / 32
+        } else {
+            1
+        }
+    }
+
+    /// Check whether a solidity type is compatible with its corresponding move type. Each array
+    /// dimension, static or dynamic, corresponds to one level of `vector`. The length of a
+    /// static array has no counterpart in the Move type and is not checked here. With
+    /// `strict_strings`, `string` is only compatible with the Move string type, since a
+    /// `vector<u8>` may hold bytes which are not valid UTF-8. References are peeled, so a
+    /// `&u64` parameter is checked like a `u64`.
+    pub(crate) fn check_type_compatibility(&self, ctx: &Context, move_ty: &Type) -> bool {
+        let move_ty = move_ty.skip_reference();
+        match self {
+            SolidityType::Primitive(p) | SolidityType::UserDefined(_, p) => {
+                p.check_primitive_type_compatibility(ctx, move_ty)
+            }
             SolidityType::DynamicArray(array_type) | SolidityType::StaticArray(array_type, _) => {
                 if let Type::Vector(ety) = move_ty {
                     array_type.check_type_compatibility(ctx, ety)
@@ -450,7 +1461,9 @@ impl SolidityType {
                 if let Type::Struct(mid, sid, _) = move_ty {
                     ctx.is_string(mid.qualified(*sid))
                 } else if let Type::Vector(ety) = move_ty {
-                    matches!(**ety, Type::Primitive(PrimitiveType::U8))
+                    // Raw bytes are not guaranteed to be valid UTF-8
+                    !ctx.options.strict_strings
+                        && matches!(**ety, Type::Primitive(PrimitiveType::U8))
                 } else {
                     false
                 }
@@ -459,44 +1472,608 @@ impl SolidityType {
                 if let Type::Vector(ety) = move_ty {
                     matches!(**ety, Type::Primitive(PrimitiveType::U8))
                 } else {
-                    false
+                    // A 32-byte word holds a Move address without truncation
+                    *self == SolidityType::BytesStatic(32)
+                        && ctx.options.allow_address_as_bytes32
+                        && move_ty.is_signer_or_address()
+                }
+            }
+            SolidityType::Tuple(tys) | SolidityType::Struct(_, tys, _) => {
+                if let Type::Struct(mid, sid, _) = move_ty {
+                    let struct_id = mid.qualified(*sid);
+                    // A named struct additionally has to agree with the name of the Move struct,
+                    // which may be qualified with its module
+                    let name_matches = match self {
+                        SolidityType::Struct(name, _, _) => {
+                            *name == ctx.get_struct_name(struct_id)
+                                || *name == ctx.get_qualified_struct_name(struct_id)
+                        }
+                        _ => true,
+                    };
+                    let field_tys = ctx.get_field_types(struct_id);
+                    name_matches
+                        && field_tys.len() == tys.len()
+                        && tys
+                            .iter()
+                            .zip(field_tys.iter())
+                            .all(|(s_ty, m_ty)| s_ty.check_type_compatibility(ctx, m_ty))
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+// ================================================================================================
+// Pretty print for SoliditySignature
+
+impl fmt::Display for SoliditySignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.selector_signature())
+    }
+}
+
+impl SoliditySignature {
+    /// The name of the default solidity signature of a move function. With the
+    /// `overload_suffixes` option, a numeric suffix is dropped from the name of the function.
+    fn default_sig_name(ctx: &Context, fun: &FunctionEnv<'_>) -> String {
+        let fun_name = fun.symbol_pool().string(fun.get_name());
+        if ctx.options.overload_suffixes {
+            strip_overload_suffix(&fun_name).to_string()
+        } else {
+            fun_name.to_string()
+        }
+    }
+
+    /// Create the default solidity signature of a move function, failing for types without a
+    /// Solidity counterpart and if it is ambiguous.
+    pub(crate) fn try_create_default_solidity_signature(
+        ctx: &Context,
+        fun: &FunctionEnv<'_>,
+    ) -> anyhow::Result<Self> {
+        Self::try_create_default_solidity_signature_with_warnings(ctx, fun, &mut vec![])
+    }
+
+    /// Create the default solidity signature of a move function like
+    /// `try_create_default_solidity_signature`, accumulating warnings about fields of structs
+    /// which are exposed by flattening them into tuples.
+    pub(crate) fn try_create_default_solidity_signature_with_warnings(
+        ctx: &Context,
+        fun: &FunctionEnv<'_>,
+        warnings: &mut Vec<String>,
+    ) -> anyhow::Result<Self> {
+        Self::check_default_solidity_signature(ctx, fun)?;
+        // implicit mapping from a move type to a solidity type, memory is used by default
+        let mut translate = |move_tys: Vec<Type>| {
+            move_tys
+                .iter()
+                .map(|move_ty| {
+                    Ok((
+                        SolidityType::try_translate_from_move_with_warnings(
+                            ctx, move_ty, warnings,
+                        )?,
+                        SignatureDataLocation::Memory,
+                    ))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()
+        };
+        let para_types = translate(fun.get_parameter_types())?;
+        let ret_types = translate(fun.get_return_types())?;
+        Ok(SoliditySignature {
+            sig_name: Self::default_sig_name(ctx, fun),
+            para_types,
+            ret_types,
+            mutability: StateMutability::NonPayable,
+            kind: SignatureKind::Function,
+            indexed_params: BTreeSet::new(),
+            para_names: BTreeMap::new(),
+            ret_names: BTreeMap::new(),
+        })
+    }
+
+    /// Check that the default solidity signature of a move function is unambiguous
+    pub(crate) fn check_default_solidity_signature(
+        ctx: &Context,
+        fun: &FunctionEnv<'_>,
+    ) -> anyhow::Result<()> {
+        for move_ty in fun
+            .get_parameter_types()
+            .iter()
+            .chain(fun.get_return_types().iter())
+        {
+            SolidityType::check_default_translation(ctx, move_ty)?;
+        }
+        Ok(())
+    }
+
+    /// Create the signature of the getter of a public state variable, like solc generates it.
+    /// The getter of a compile-time constant is `pure`, otherwise it reads storage and is `view`.
+    /// TODO: getters of arrays and mappings, which take the index or key as parameter
+    pub fn getter_signature(name: &str, ty: SolidityType, is_constant: bool) -> Self {
+        SoliditySignature {
+            sig_name: name.to_string(),
+            para_types: vec![],
+            ret_types: vec![(ty, SignatureDataLocation::Memory)],
+            mutability: if is_constant {
+                StateMutability::Pure
+            } else {
+                StateMutability::View
+            },
+            kind: SignatureKind::Function,
+            indexed_params: BTreeSet::new(),
+            para_names: BTreeMap::new(),
+            ret_names: BTreeMap::new(),
+        }
+    }
+
+    /// Check that only the parameters of an event are indexed, and at most `MAX_INDEXED_PARAMS`
+    /// of them.
+    fn check_indexed_params(&self) -> anyhow::Result<()> {
+        if self.indexed_params.is_empty() {
+            return Ok(());
+        }
+        if self.kind != SignatureKind::Event {
+            return Err(anyhow!("`indexed` is only allowed for event parameters"));
+        }
+        if self.indexed_params.len() > MAX_INDEXED_PARAMS {
+            return Err(anyhow!(
+                "an event can have at most {} indexed parameters",
+                MAX_INDEXED_PARAMS
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check that the signature has a name, as otherwise its selector would be malformed.
+    /// Special functions like receive and fallback are dispatched without a signature.
+    pub fn check_sig_name(&self) -> anyhow::Result<()> {
+        if self.sig_name.is_empty() {
+            Err(anyhow!(
+                "the function name of a signature must not be empty"
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Suggest the move parameter types of a function with this signature, with None for those
+    /// for which `suggest_move_type` has no suggestion
+    pub fn expected_move_params(&self, ctx: &Context) -> Vec<Option<Type>> {
+        self.para_types
+            .iter()
+            .map(|(ty, _)| ty.suggest_move_type(ctx))
+            .collect()
+    }
+
+    /// Compute the size of the ABI encoded parameters, without the selector. For each dynamic
+    /// parameter, `dynamic_lengths` must give a pair of its index and its length, i.e. the number
+    /// of bytes or of array elements. Only dynamic parameters of `bytes`, `string` or arrays of
+    /// static elements are supported.
+    pub fn exact_calldata_size(&self, dynamic_lengths: &[(usize, usize)]) -> anyhow::Result<usize> {
+        for (idx, _) in dynamic_lengths {
+            match self.para_types.get(*idx) {
+                Some((ty, _)) if !ty.is_static() => {}
+                _ => return Err(anyhow!("parameter {} is not a dynamic parameter", idx)),
+            }
+        }
+        let mut size = 0;
+        for (idx, (ty, _)) in self.para_types.iter().enumerate() {
+            size += ty.abi_head_size(true);
+            if ty.is_static() {
+                continue;
+            }
+            let len = dynamic_lengths
+                .iter()
+                .find(|(i, _)| *i == idx)
+                .map(|(_, len)| *len)
+                .ok_or_else(|| anyhow!("missing length of dynamic parameter {}", idx))?;
+            size += match ty {
+                SolidityType::Bytes | SolidityType::SolidityString => ty.tail_size_for_len(len),
+                SolidityType::DynamicArray(ety) if ety.is_static() => ty.tail_size_for_len(len),
+                _ => {
+                    return Err(anyhow!(
+                        "size of dynamic parameter {} of type `{}` depends on more than its length",
+                        idx,
+                        ty
+                    ))
+                }
+            };
+        }
+        Ok(size)
+    }
+
+    /// Compute an upper bound of the calldata gas of a call, assuming all bytes of the selector
+    /// and the encoded parameters are nonzero. See `exact_calldata_size` for `dynamic_lengths`.
+    pub fn worst_case_calldata_gas(
+        &self,
+        dynamic_lengths: &[(usize, usize)],
+    ) -> anyhow::Result<u64> {
+        const GAS_PER_NONZERO_BYTE: u64 = 16;
+        let size = 4 + self.exact_calldata_size(dynamic_lengths)?;
+        Ok(size as u64 * GAS_PER_NONZERO_BYTE)
+    }
+
+    /// Compute the smallest length of calldata for a call of this function, including the
+    /// selector, as reached when all dynamic parameters are empty.
+    pub fn min_calldata_size(&self) -> usize {
+        let tys = self
+            .para_types
+            .iter()
+            .map(|(ty, _)| ty.clone())
+            .collect_vec();
+        4 + SolidityType::Tuple(tys).min_encoded_size()
+    }
+
+    /// Check that calldata of the given length, including the selector, is not shorter than
+    /// `min_calldata_size`.
+    pub fn validate_calldata_len(&self, calldata_len: usize) -> Result<(), String> {
+        let min_size = self.min_calldata_size();
+        if calldata_len < min_size {
+            Err(format!(
+                "calldata of {} bytes is shorter than the minimum of {} bytes for `{}`",
+                calldata_len, min_size, self
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Roughly estimate the gas for decoding each parameter, labeled by its type. This is only
+    /// meant to compare parameters: static parameters cost a `calldataload` per word, while
+    /// dynamic parameters additionally pay a base cost for following the offset and allocating
+    /// memory, and the copy of a length word and a single payload word, since the actual length
+    /// is not known.
+    pub fn decode_cost_estimate(&self) -> Vec<(String, u64)> {
+        const GAS_PER_LOADED_WORD: u64 = 3;
+        const GAS_PER_COPIED_WORD: u64 = 6;
+        const GAS_DYNAMIC_BASE: u64 = 100;
+        self.para_types
+            .iter()
+            .map(|(ty, _)| {
+                let head_words = (ty.abi_head_size(true) / 32) as u64;
+                let mut cost = head_words * GAS_PER_LOADED_WORD;
+                if !ty.is_static() {
+                    cost += GAS_DYNAMIC_BASE + 2 * GAS_PER_COPIED_WORD;
+                }
+                (ty.to_string(), cost)
+            })
+            .collect()
+    }
+
+    /// Check whether the function can receive Ether, i.e. a nonzero call value
+    pub fn accepts_value(&self) -> bool {
+        self.mutability == StateMutability::Payable
+    }
+
+    /// Returns the name and parameter types of this signature as they were written, see
+    /// `SolidityType::to_source_string`. The selector is always computed from the canonical form.
+    pub fn to_source_string(&self) -> String {
+        format!(
+            "{}({})",
+            self.sig_name,
+            self.para_types
+                .iter()
+                .map(|(ty, _)| ty.to_source_string())
+                .join(",")
+        )
+    }
+
+    /// Returns the fully explicit form of this signature for audits, in which every alias is
+    /// expanded and structs are written as tuples. This is exactly the string hashed into the
+    /// selector.
+    pub fn explicit_signature(&self) -> String {
+        self.selector_signature()
+    }
+
+    /// Returns a debug-friendly form of this signature in which the components of named structs
+    /// carry their field names, e.g. `transfer((address to, uint256 amount))`. This form is
+    /// never hashed; use `selector_signature` for that.
+    pub fn annotated_signature(&self) -> String {
+        format!(
+            "{}({})",
+            self.sig_name,
+            self.para_types
+                .iter()
+                .map(|(ty, _)| ty.to_annotated_string())
+                .join(", ")
+        )
+    }
+
+    /// Classify the role of this function: a `view` or `pure` function with return values is a
+    /// getter, regardless of its parameters; otherwise, a function with parameters and without
+    /// return values is a setter; all remaining functions are actions.
+    pub fn classify(&self) -> FunctionRole {
+        let read_only = matches!(
+            self.mutability,
+            StateMutability::View | StateMutability::Pure
+        );
+        if read_only && self.has_returns() {
+            FunctionRole::Getter
+        } else if !read_only && !self.para_types.is_empty() && !self.has_returns() {
+            FunctionRole::Setter
+        } else {
+            FunctionRole::Action
+        }
+    }
+
+    /// Returns a comment for each parameter giving the calldata offset of its head, after the
+    /// 4-byte selector. For dynamic parameters, the head is the slot holding the offset of the
+    /// tail.
+    pub fn offset_comments(&self) -> Vec<String> {
+        let mut offset = 4;
+        let mut comments = vec![];
+        for (i, (ty, _)) in self.para_types.iter().enumerate() {
+            let head = if ty.is_static() { "" } else { " offset-slot" };
+            comments.push(format!(
+                "// param{} ({}){} at 0x{:02x}",
+                i, ty, head, offset
+            ));
+            offset += ty.abi_head_size(true);
+        }
+        comments
+    }
+
+    /// Check whether the function has any return values
+    pub fn has_returns(&self) -> bool {
+        !self.ret_types.is_empty()
+    }
+
+    /// Compute the overall head size of the parameters, see `abi_head_sizes_sum`.
+    pub fn params_head_size(&self, padded: bool) -> usize {
+        let tys = self
+            .para_types
+            .iter()
+            .map(|(ty, _)| ty.clone())
+            .collect_vec();
+        abi_head_sizes_sum(&tys, padded)
+    }
+
+    /// Compute the number of 32-byte words the parameters occupy in the head of the calldata
+    /// after the selector. As in solc's layout, a dynamic parameter occupies one word for its
+    /// offset, while a static tuple or static array is inlined and occupies one word per
+    /// element, so this may exceed the number of parameters.
+    pub fn params_head_words(&self) -> usize {
+        self.params_head_size(true) / 32
+    }
+
+    /// Returns a hex skeleton of the calldata of a call, for filling in concrete values: the
+    /// selector followed by the head words of the parameters, where words of static parameters
+    /// are filled with `XX` and offsets of dynamic parameters with `??`. The tails of dynamic
+    /// parameters are not included.
+    pub fn calldata_template(&self) -> String {
+        let mut template = format!("0x{}", hex::encode(self.selector()));
+        for (ty, _) in &self.para_types {
+            if ty.is_static() {
+                template.push_str(&"XX".repeat(ty.abi_head_size(true)));
+            } else {
+                template.push_str(&"??".repeat(32));
+            }
+        }
+        template
+    }
+
+    /// Compute the overall head size of the return values, see `abi_head_sizes_sum`.
+    pub fn returns_head_size(&self, padded: bool) -> usize {
+        let tys = self
+            .ret_types
+            .iter()
+            .map(|(ty, _)| ty.clone())
+            .collect_vec();
+        abi_head_sizes_sum(&tys, padded)
+    }
+
+    /// Estimate the number of stack slots needed to hold the parameters and return values in
+    /// naive code generation: one per word for static types and one per dynamic type, which is
+    /// passed as a memory pointer.
+    pub fn estimated_stack_slots(&self) -> usize {
+        self.para_types
+            .iter()
+            .chain(self.ret_types.iter())
+            .map(|(ty, _)| ty.stack_slots())
+            .sum()
+    }
+
+    /// Check whether the estimated stack slots exceed what the EVM can reach, in which case
+    /// values should be spilled to memory
+    pub fn exceeds_stack_limit(&self) -> bool {
+        self.estimated_stack_slots() > MAX_STACK_SLOTS
+    }
+
+    /// Check whether the return values alone need more stack slots than the EVM can reach, in
+    /// which case they have to be returned via memory. Slots are counted as in
+    /// `estimated_stack_slots`.
+    pub fn returns_exceed_stack_limit(&self) -> bool {
+        self.ret_types
+            .iter()
+            .map(|(ty, _)| ty.stack_slots())
+            .sum::<usize>()
+            > MAX_STACK_SLOTS
+    }
+
+    /// Check whether calling this function requires ABI coder v2, which is the case if any
+    /// parameter or return type contains a tuple or an array of dynamic elements
+    pub fn requires_abi_v2(&self) -> bool {
+        let mut requires_v2 = false;
+        for (ty, _) in self.para_types.iter().chain(self.ret_types.iter()) {
+            ty.visit(&mut |t| {
+                requires_v2 |= matches!(t, SolidityType::Tuple(_) | SolidityType::Struct(_, _, _))
+                    || t.array_element_is_dynamic() == Some(true)
+            });
+        }
+        requires_v2
+    }
+
+    /// Returns the pragmas for declaring this signature in a Solidity interface. The version is
+    /// always 0.8.0 or later; although ABI coder v2 is the default there, it is still requested
+    /// explicitly when the signature needs it.
+    pub fn min_solidity_pragma(&self) -> SolidityPragma {
+        SolidityPragma {
+            version: ">=0.8.0",
+            needs_abicoder_v2: self.requires_abi_v2(),
+        }
+    }
+
+    /// Returns an equivalent signature which doesn't require ABI coder v2, by flattening tuple
+    /// parameters and return values into their components. Returns None if this isn't possible,
+    /// e.g. for nested tuples or arrays of tuples, or for events whose tuple parameters would
+    /// be flattened while some parameters are indexed. Note that flattening changes the selector.
+    pub fn downgrade_to_v1(&self) -> Option<SoliditySignature> {
+        let flatten = |tys: &[(SolidityType, SignatureDataLocation)]| {
+            let mut flattened = vec![];
+            for (ty, loc) in tys {
+                if let SolidityType::Tuple(comps) | SolidityType::Struct(_, comps, _) = ty {
+                    flattened.extend(comps.iter().map(|comp| {
+                        if comp.is_value_type() {
+                            (comp.clone(), SignatureDataLocation::Memory)
+                        } else {
+                            (comp.clone(), loc.clone())
+                        }
+                    }));
+                } else {
+                    flattened.push((ty.clone(), loc.clone()));
                 }
             }
-            SolidityType::Tuple(_) => panic!("unexpected solidity type"),
+            flattened
+        };
+        let sig = SoliditySignature {
+            sig_name: self.sig_name.clone(),
+            para_types: flatten(&self.para_types),
+            ret_types: flatten(&self.ret_types),
+            mutability: self.mutability,
+            kind: self.kind,
+            indexed_params: self.indexed_params.clone(),
+            // Names would refer to the wrong positions after flattening
+            para_names: if flatten(&self.para_types).len() == self.para_types.len() {
+                self.para_names.clone()
+            } else {
+                BTreeMap::new()
+            },
+            ret_names: if flatten(&self.ret_types).len() == self.ret_types.len() {
+                self.ret_names.clone()
+            } else {
+                BTreeMap::new()
+            },
+        };
+        if sig.requires_abi_v2()
+            || (!sig.indexed_params.is_empty() && sig.para_types.len() != self.para_types.len())
+        {
+            None
+        } else {
+            Some(sig)
         }
     }
-}
-
-// ================================================================================================
-// Pretty print for SoliditySignature
 
-impl fmt::Display for SoliditySignature {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.selector_signature())
+    /// Check whether two signatures have the same parameter and return types, ignoring the
+    /// function names and data locations
+    pub fn shape_eq(&self, other: &SoliditySignature) -> bool {
+        let types_eq =
+            |tys: &[(SolidityType, SignatureDataLocation)],
+             other_tys: &[(SolidityType, SignatureDataLocation)]| {
+                tys.len() == other_tys.len()
+                    && tys
+                        .iter()
+                        .zip(other_tys.iter())
+                        .all(|((ty, _), (other_ty, _))| ty.normalize() == other_ty.normalize())
+            };
+        types_eq(&self.para_types, &other.para_types) && types_eq(&self.ret_types, &other.ret_types)
     }
-}
 
-impl SoliditySignature {
-    /// Create a default solidity signature from a move function signature
-    pub fn create_default_solidity_signature(ctx: &Context, fun: &FunctionEnv<'_>) -> Self {
-        let fun_name = fun.symbol_pool().string(fun.get_name()).to_string();
-        let mut para_type_lst = vec![];
-        for move_ty in fun.get_parameter_types() {
-            let solidity_ty = SolidityType::translate_from_move(ctx, &move_ty); // implicit mapping from a move type to a solidity type
-            para_type_lst.push((solidity_ty, SignatureDataLocation::Memory)); // memory is used by default
-        }
-        let mut ret_type_lst = vec![];
-        for move_ty in fun.get_return_types() {
-            let solidity_ty = SolidityType::translate_from_move(ctx, &move_ty);
-            ret_type_lst.push((solidity_ty, SignatureDataLocation::Memory));
+    /// Returns the sorted names of all named types referenced by the parameter and return types
+    pub fn referenced_type_names(&self) -> Vec<String> {
+        let mut names = BTreeSet::new();
+        for (ty, _) in self.para_types.iter().chain(self.ret_types.iter()) {
+            ty.visit(&mut |t| names.extend(t.type_name()));
         }
-        SoliditySignature {
-            sig_name: fun_name,
-            para_types: para_type_lst,
-            ret_types: ret_type_lst,
+        names.into_iter().collect()
+    }
+
+    /// Returns the four-byte selector of this signature, the first bytes of the keccak-256 hash
+    /// of its canonical form in which aliases like `uint` and struct names are resolved.
+    pub fn selector(&self) -> [u8; 4] {
+        selector_of(&self.selector_signature())
+    }
+
+    /// Returns the topic of this signature if it declares an event, which is the full hash of
+    /// its canonical form.
+    pub fn event_topic(&self) -> [u8; 32] {
+        Keccak.hash(self.selector_signature().as_bytes())
+    }
+
+    /// Returns the bytes identifying this signature: the 32-byte topic of an event, or the
+    /// 4-byte selector of a function or error, which is the first word of its revert data.
+    pub fn identifier(&self) -> Vec<u8> {
+        match self.kind {
+            SignatureKind::Event => self.event_topic().to_vec(),
+            SignatureKind::Function | SignatureKind::Error => self.selector().to_vec(),
         }
     }
 
+    /// Returns the selector of this signature as `0x` followed by 8 hex digits
+    pub fn selector_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.selector()))
+    }
+
+    /// Same as `selector_hex`.
+    pub fn selector_string(&self) -> String {
+        self.selector_hex()
+    }
+
+    /// Check whether the selector of this signature is all zeros, which dispatchers commonly
+    /// confuse with the fallback path.
+    pub fn has_zero_selector(&self) -> bool {
+        self.has_zero_selector_with(&Keccak)
+    }
+
+    /// Check whether the selector of this signature computed with the given hasher is all zeros.
+    pub fn has_zero_selector_with(&self, hasher: &impl Keccak256) -> bool {
+        selector_of_with(hasher, &self.selector_signature()) == [0u8; 4]
+    }
+
+    /// Returns the standard function in `KNOWN_SELECTORS` whose selector this signature shares
+    /// without being that function, which likely shadows the standard interface by accident.
+    pub fn collides_with_standard(&self) -> Option<&'static str> {
+        let selector = self.selector();
+        let canonical = self.selector_signature();
+        KNOWN_SELECTORS
+            .iter()
+            .find(|(known, sig)| *known == selector && *sig != canonical)
+            .map(|(_, sig)| *sig)
+    }
+
+    /// Check whether the selector of this signature equals the given one, which must be
+    /// written as `0x` followed by 8 hex digits
+    pub fn matches_selector_hex(&self, hex: &str) -> anyhow::Result<bool> {
+        let digits = hex
+            .strip_prefix("0x")
+            .filter(|d| d.len() == 8)
+            .ok_or_else(|| anyhow!("selector `{}` is not `0x` followed by 8 hex digits", hex))?;
+        let expected = hex::decode(digits)
+            .map_err(|e| anyhow!("selector `{}` is not valid hex: {}", hex, e))?;
+        Ok(expected == self.selector())
+    }
+
+    /// Returns the Rust tuple type approximating the parameter types, e.g. `(U256, Address)`
+    pub fn rust_param_tuple(&self) -> String {
+        rust_tuple_type(self.para_types.iter().map(|(ty, _)| ty))
+    }
+
+    /// Returns the canonical type strings of the parameters, e.g. `["address", "uint256"]`
+    pub fn param_type_strings(&self) -> Vec<String> {
+        self.para_types
+            .iter()
+            .map(|(ty, _)| ty.to_string())
+            .collect()
+    }
+
+    /// Returns the canonical type strings of the return values
+    pub fn return_type_strings(&self) -> Vec<String> {
+        self.ret_types
+            .iter()
+            .map(|(ty, _)| ty.to_string())
+            .collect()
+    }
+
     /// Generate parameter list for computing the function selector
     fn compute_param_types(&self, param_types: &[&SolidityType]) -> String {
         let display_type_slice = |tys: &[&SolidityType]| -> String {
@@ -518,109 +2095,294 @@ impl SoliditySignature {
 
     /// Parse the solidity signature
     pub fn parse_into_solidity_signature(sig_str: &str) -> anyhow::Result<Self> {
-        // Solidity signature matching
+        Self::parse_with_options(sig_str, &SignatureParseOptions::default())
+    }
+
+    /// Parse the solidity signature with the given parse options
+    pub fn parse_with_options(
+        sig_str: &str,
+        options: &SignatureParseOptions,
+    ) -> anyhow::Result<Self> {
+        // Solidity signature matching, the parameter list is delimited by matching parentheses
+        // since it may contain nested tuple types
         static SIG_REG: Lazy<Regex> = Lazy::new(|| {
-            Regex::new(
-                r"^\s*(?P<sig_name>[a-zA-Z_$][a-zA-Z_$0-9]*)\s*\((?P<args>[^)]*)\)(?P<ret_ty>.*)?",
-            )
-            .unwrap()
+            Regex::new(r"^\s*(?P<sig_name>[a-zA-Z_$][a-zA-Z_$0-9]*)\s*(?P<args>\()").unwrap()
         });
-        if let Some(parsed) = SIG_REG.captures(sig_str.trim()) {
+        let mut sig_str_trim = sig_str.trim();
+        let mut kind = SignatureKind::Function;
+        if let Some(rest) = strip_word(sig_str_trim, "event") {
+            kind = SignatureKind::Event;
+            sig_str_trim = rest.trim_start();
+        } else if let Some(rest) = strip_word(sig_str_trim, "error") {
+            kind = SignatureKind::Error;
+            sig_str_trim = rest.trim_start();
+        }
+        if let Some(parsed) = SIG_REG.captures(sig_str_trim) {
             let sig_name = parsed.name("sig_name").context(PARSE_ERR_MSG)?.as_str();
-            let para_type_str = parsed.name("args").context(PARSE_ERR_MSG)?.as_str();
-            let ret_ty_str_opt = parsed.name("ret_ty");
+            let args_start = parsed.name("args").context(PARSE_ERR_MSG)?.start();
+            let args_end = find_matching_paren(sig_str_trim, args_start).context(PARSE_ERR_MSG)?;
+            let para_type_str = &sig_str_trim[args_start + 1..args_end];
+            let ret_ty_str = &sig_str_trim[args_end + 1..];
             let mut ret_ty = "";
-            if let Some(ret_ty_str) = ret_ty_str_opt {
-                let ret_ty_str_trim = ret_ty_str.as_str().trim();
-                if !ret_ty_str_trim.is_empty() {
-                    let mut parse_error = false;
-                    if let Some(stripped_returns) = ret_ty_str_trim.strip_prefix("returns") {
-                        let stripped_returns_trim = stripped_returns.trim();
-                        if stripped_returns_trim.starts_with('(')
-                            && stripped_returns_trim.ends_with(')')
+            let mut mutability = StateMutability::NonPayable;
+            let mut ret_ty_str_trim = ret_ty_str.trim();
+            // Visibility has no effect on the signature and is skipped
+            let mut mutability_declared = false;
+            loop {
+                if let Some(rest) = strip_visibility(ret_ty_str_trim) {
+                    ret_ty_str_trim = rest.trim();
+                } else if let Some((declared, rest)) =
+                    StateMutability::strip_keyword(ret_ty_str_trim)
+                {
+                    if mutability_declared {
+                        return Err(anyhow!("state mutability is specified more than once"));
+                    }
+                    mutability_declared = true;
+                    mutability = declared;
+                    ret_ty_str_trim = rest.trim();
+                } else {
+                    break;
+                }
+            }
+            if !ret_ty_str_trim.is_empty() {
+                let mut parse_error = false;
+                if let Some(stripped_returns) = ret_ty_str_trim.strip_prefix("returns") {
+                    let stripped_returns_trim = stripped_returns.trim();
+                    let ret_end_opt = if stripped_returns_trim.starts_with('(') {
+                        find_matching_paren(stripped_returns_trim, 0)
+                    } else {
+                        None
+                    };
+                    if let Some(ret_end) = ret_end_opt {
+                        let trailing = stripped_returns_trim[ret_end + 1..].trim();
+                        if trailing.is_empty() {
+                            ret_ty = &stripped_returns_trim[1..ret_end];
+                        } else if strip_visibility(trailing).is_some()
+                            || StateMutability::strip_keyword(trailing).is_some()
                         {
-                            ret_ty = &stripped_returns_trim[1..stripped_returns_trim.len() - 1];
+                            return Err(anyhow!(PARSE_ERR_MSG_MODIFIER_ORDER));
+                        } else if trailing.strip_prefix("returns").map_or(false, |rest| {
+                            rest.is_empty()
+                                || rest.starts_with(|c: char| c.is_whitespace() || c == '(')
+                        }) {
+                            return Err(anyhow!(PARSE_ERR_MSG_DUPLICATE_RETURNS));
                         } else {
-                            parse_error = true;
+                            return Err(unexpected_trailing_tokens(trailing));
                         }
                     } else {
                         parse_error = true;
                     }
-                    if parse_error {
-                        return Err(anyhow!(PARSE_ERR_MSG_RETURN));
-                    }
+                } else {
+                    return Err(unexpected_trailing_tokens(ret_ty_str_trim));
+                }
+                if parse_error {
+                    return Err(anyhow!(PARSE_ERR_MSG_RETURN));
                 }
             }
+            if kind != SignatureKind::Function && (mutability_declared || !ret_ty.is_empty()) {
+                return Err(anyhow!(
+                    "state mutability and return types are only allowed for functions"
+                ));
+            }
+            let mut indexed_params = BTreeSet::new();
+            let mut para_names = BTreeMap::new();
+            let mut ret_names = BTreeMap::new();
             let solidity_sig = SoliditySignature {
                 sig_name: sig_name.to_string(),
-                para_types: SoliditySignature::extract_para_type_str(para_type_str)?,
-                ret_types: SoliditySignature::extract_para_type_str(ret_ty)?,
+                para_types: SoliditySignature::extract_para_type_str(
+                    sig_str,
+                    para_type_str,
+                    options,
+                    if kind == SignatureKind::Event {
+                        Some(&mut indexed_params)
+                    } else {
+                        None
+                    },
+                    &mut para_names,
+                )?,
+                ret_types: SoliditySignature::extract_para_type_str(
+                    sig_str,
+                    ret_ty,
+                    options,
+                    None,
+                    &mut ret_names,
+                )?,
+                mutability,
+                kind,
+                indexed_params,
+                para_names,
+                ret_names,
             };
+            solidity_sig.check_indexed_params()?;
             Ok(solidity_sig)
         } else {
-            Err(anyhow!(PARSE_ERR_MSG))
+            Err(anyhow!(SoliditySignature::diagnose_sig_head(sig_str)))
+        }
+    }
+
+    /// Explain why the head of a signature, i.e. the function name followed by the opening
+    /// parenthesis, could not be recognized. Positions are byte offsets into `sig_str`.
+    fn diagnose_sig_head(sig_str: &str) -> String {
+        let name_start = sig_str.len() - sig_str.trim_start().len();
+        let name_end = match sig_str.find('(') {
+            Some(pos) => pos,
+            None => return "missing opening parenthesis".to_string(),
+        };
+        let name = sig_str[name_start..name_end].trim_end();
+        if name.is_empty() {
+            return "missing function name".to_string();
         }
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            return "function name cannot start with a digit".to_string();
+        }
+        for (i, c) in name.char_indices() {
+            let valid =
+                c.is_ascii_alphabetic() || c == '_' || c == '$' || (i > 0 && c.is_ascii_digit());
+            if !valid {
+                return format!(
+                    "function name contains invalid character `{}` at position {}",
+                    c,
+                    name_start + i
+                );
+            }
+        }
+        PARSE_ERR_MSG.to_string()
     }
 
-    /// Generate pairs of solidity type and location
+    /// Generate pairs of solidity type and location. Errors about a parameter carry a
+    /// `SignatureErrorSpan` locating it in `sig_str`, of which `args` must be a part.
+    /// The positions of parameters marked as `indexed` are added to `indexed`, which must only
+    /// be given for the parameters of an event, and the names of named parameters to `names`.
     fn extract_para_type_str(
+        sig_str: &str,
         args: &str,
+        options: &SignatureParseOptions,
+        mut indexed: Option<&mut BTreeSet<usize>>,
+        names: &mut BTreeMap<usize, String>,
     ) -> anyhow::Result<Vec<(SolidityType, SignatureDataLocation)>> {
         let args_trim = args.trim();
         if args_trim.is_empty() {
             return Ok(vec![]);
         }
         let mut ret_vec = vec![];
-        let paras = args_trim.split(',').collect_vec();
-        for para in paras {
-            let para_trim = para.trim();
-            if para_trim.is_empty() {
-                return Err(anyhow!(PARSE_ERR_MSG));
-            }
-            let mut data_location = SignatureDataLocation::Memory;
-            let mut para_type_str = para_trim;
-            let mut loc_flag = false;
-            if let Some(stripped_memory) = para_trim.strip_suffix("memory") {
-                data_location = SignatureDataLocation::Memory;
-                para_type_str = stripped_memory;
-                loc_flag = true;
-            } else if let Some(_stripped_calldata) = para_trim.strip_suffix("calldata") {
-                return Err(anyhow!("calldata is not supported yet"));
-            }
-            let ty = SolidityType::parse(para_type_str)?;
-            if loc_flag && ty.is_value_type() {
+        let paras = split_top_level_commas(args_trim)?;
+        for (idx, para) in paras.into_iter().enumerate() {
+            let mut para_trim = para.trim();
+            let para_offset = substr_offset(sig_str, para) + (para.len() - para.trim_start().len());
+            if let Some((stripped, name)) = strip_param_name(para_trim) {
+                names.insert(idx, name.to_string());
+                para_trim = stripped;
+            }
+            if let Some(stripped) = strip_indexed(para_trim) {
+                match indexed.as_mut() {
+                    Some(indexed) => {
+                        indexed.insert(idx);
+                        para_trim = stripped;
+                    }
+                    None => {
+                        let message = "`indexed` is only allowed for event parameters".to_string();
+                        return Err(anyhow!(message.clone()).context(SignatureErrorSpan {
+                            offset: para_offset + para_trim.len() - "indexed".len(),
+                            len: "indexed".len(),
+                            message,
+                        }));
+                    }
+                }
+            }
+            let para_ty = Self::extract_para_type(para_trim, options).map_err(|err| {
+                // Narrow the span to the offending part of the type if it is known
+                let (offset, len) = match err.downcast_ref::<SolidityTypeError>().and_then(|e| {
+                    para_trim
+                        .find(e.offending())
+                        .map(|pos| (pos, e.offending()))
+                }) {
+                    Some((pos, offending)) => (para_offset + pos, offending.len()),
+                    None => (para_offset, para_trim.len()),
+                };
+                let message = err.to_string();
+                err.context(SignatureErrorSpan {
+                    offset,
+                    len,
+                    message,
+                })
+            })?;
+            ret_vec.push(para_ty);
+        }
+        Ok(ret_vec)
+    }
+
+    /// Generate the solidity type and location of a single parameter
+    fn extract_para_type(
+        para_trim: &str,
+        options: &SignatureParseOptions,
+    ) -> anyhow::Result<(SolidityType, SignatureDataLocation)> {
+        if para_trim.is_empty() {
+            return Err(anyhow!(PARSE_ERR_MSG));
+        }
+        let mut data_location = SignatureDataLocation::Memory;
+        let mut para_type_str = para_trim;
+        let mut loc_flag = false;
+        if let Some(stripped_memory) = strip_location(para_trim, "memory")? {
+            data_location = SignatureDataLocation::Memory;
+            para_type_str = stripped_memory;
+            loc_flag = true;
+        } else if let Some(stripped_calldata) = strip_location(para_trim, "calldata")? {
+            data_location = SignatureDataLocation::CallData;
+            para_type_str = stripped_calldata;
+            loc_flag = true;
+        }
+        let ty = SolidityType::parse(para_type_str, options)?;
+        if loc_flag && ty.is_value_type() {
+            return Err(anyhow!(
+                "data location can only be specified for array or struct types"
+            ));
+        }
+        if !loc_flag && !ty.is_value_type() {
+            data_location = options.default_reference_location.clone();
+        }
+        Ok((ty, data_location))
+    }
+
+    /// Check that all parameter and return types can be represented in Move, so that a
+    /// compatibility check can fail with a reason instead of only being false.
+    pub fn check_move_representable(&self) -> anyhow::Result<()> {
+        for (ty, _) in &self.para_types {
+            if !ty.is_move_representable() {
                 return Err(anyhow!(
-                    "data location can only be specified for array or struct types"
+                    "parameter type `{}` has no Move representation",
+                    ty
                 ));
             }
-            ret_vec.push((ty, data_location));
         }
-        Ok(ret_vec)
+        for (ty, _) in &self.ret_types {
+            if !ty.is_move_representable() {
+                return Err(anyhow!("return type `{}` has no Move representation", ty));
+            }
+        }
+        Ok(())
     }
 
     /// Check whether the user defined solidity signature is compatible with the Move signature
-    pub fn check_sig_compatibility(&self, ctx: &Context, fun: &FunctionEnv<'_>) -> bool {
+    pub(crate) fn check_sig_compatibility(&self, ctx: &Context, fun: &FunctionEnv<'_>) -> bool {
         let para_types = fun.get_parameter_types();
-        let sig_para_vec = self.para_types.iter().map(|(ty, _)| ty).collect::<Vec<_>>();
-        if para_types.len() != sig_para_vec.len() {
+        if para_types.len() != self.para_types.len() {
             return false;
         }
         // Check parameter type list
-        for type_pair in para_types.iter().zip(sig_para_vec.iter()) {
-            let (m_ty, s_ty) = type_pair;
-            if !s_ty.check_type_compatibility(ctx, m_ty) {
+        for (m_ty, (s_ty, loc)) in para_types.iter().zip(self.para_types.iter()) {
+            if !s_ty.check_location_compatibility(loc) || !s_ty.check_type_compatibility(ctx, m_ty)
+            {
                 return false;
             }
         }
         // Check return type list
-        let sig_ret_vec = self.ret_types.iter().map(|(ty, _)| ty).collect::<Vec<_>>();
         let ret_types = fun.get_return_types();
-        if ret_types.len() != sig_ret_vec.len() {
+        if ret_types.len() != self.ret_types.len() {
             return false;
         }
-        for type_pair in ret_types.iter().zip(sig_ret_vec.iter()) {
-            let (m_ty, s_ty) = type_pair;
-            if !s_ty.check_type_compatibility(ctx, m_ty) {
+        for (m_ty, (s_ty, loc)) in ret_types.iter().zip(self.ret_types.iter()) {
+            if !s_ty.check_location_compatibility(loc) || !s_ty.check_type_compatibility(ctx, m_ty)
+            {
                 return false;
             }
         }
@@ -628,10 +2390,312 @@ impl SoliditySignature {
     }
 }
 
+// ================================================================================================
+// Builder for SoliditySignature
+
+/// Assembles a `SoliditySignature` from its parts, which are validated on `build`.
+#[derive(Debug, Default)]
+pub struct SoliditySignatureBuilder {
+    sig_name: String,
+    para_types: Vec<(SolidityType, SignatureDataLocation)>,
+    ret_types: Vec<SolidityType>,
+    mutability: Option<StateMutability>,
+    kind: Option<SignatureKind>,
+    indexed_params: BTreeSet<usize>,
+}
+
+impl SoliditySignatureBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.sig_name = name.to_string();
+        self
+    }
+
+    pub fn param(mut self, ty: SolidityType, location: SignatureDataLocation) -> Self {
+        self.para_types.push((ty, location));
+        self
+    }
+
+    /// Add an `indexed` parameter of an event, which is always in memory.
+    pub fn indexed_param(mut self, ty: SolidityType) -> Self {
+        self.indexed_params.insert(self.para_types.len());
+        self.para_types.push((ty, SignatureDataLocation::Memory));
+        self
+    }
+
+    /// Add a return type, which is always in memory.
+    pub fn ret(mut self, ty: SolidityType) -> Self {
+        self.ret_types.push(ty);
+        self
+    }
+
+    /// Set the state mutability, which is `nonpayable` by default.
+    pub fn mutability(mut self, mutability: StateMutability) -> Self {
+        self.mutability = Some(mutability);
+        self
+    }
+
+    /// Set what the signature declares, which is a function by default.
+    pub fn kind(mut self, kind: SignatureKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Build the signature, checking that it has a name, that all types are valid, that data
+    /// locations other than memory are only given for reference types, and that only events
+    /// have indexed parameters.
+    pub fn build(self) -> anyhow::Result<SoliditySignature> {
+        for (ty, location) in &self.para_types {
+            ty.check_abi_valid()?;
+            if ty.is_value_type() && *location != SignatureDataLocation::Memory {
+                return Err(anyhow!(
+                    "data location can only be specified for array or struct types"
+                ));
+            }
+        }
+        for ty in &self.ret_types {
+            ty.check_abi_valid()?;
+        }
+        let sig = SoliditySignature {
+            sig_name: self.sig_name,
+            para_types: self.para_types,
+            ret_types: self
+                .ret_types
+                .into_iter()
+                .map(|ty| (ty, SignatureDataLocation::Memory))
+                .collect(),
+            mutability: self.mutability.unwrap_or(StateMutability::NonPayable),
+            kind: self.kind.unwrap_or(SignatureKind::Function),
+            indexed_params: self.indexed_params,
+            para_names: BTreeMap::new(),
+            ret_names: BTreeMap::new(),
+        };
+        sig.check_sig_name()?;
+        sig.check_indexed_params()?;
+        Ok(sig)
+    }
+}
+
+// ================================================================================================
+// ABI JSON for SoliditySignature
+
+impl SolidityType {
+    /// Returns the `type` of this type in ABI JSON, where tuples are written as `tuple` and their
+    /// component types are given separately.
+    pub fn abi_type(&self) -> String {
+        match self {
+            SolidityType::Tuple(_) | SolidityType::Struct(_, _, _) => "tuple".to_string(),
+            SolidityType::DynamicArray(ty) => format!("{}[]", ty.abi_type()),
+            SolidityType::StaticArray(ty, n) => format!("{}[{}]", ty.abi_type(), n),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Returns the `internalType` of this type in ABI JSON, which differs from `abi_type` in
+    /// naming structs, as in `struct Name[]`, and user-defined value types, and in keeping
+    /// `address payable`.
+    pub fn abi_internal_type(&self) -> String {
+        match self {
+            SolidityType::Struct(name, _, _) => format!("struct {}", name),
+            SolidityType::UserDefined(name, _) => name.clone(),
+            SolidityType::Primitive(SolidityPrimitiveType::Address(true)) => {
+                "address payable".to_string()
+            }
+            SolidityType::DynamicArray(ty) => format!("{}[]", ty.abi_internal_type()),
+            SolidityType::StaticArray(ty, n) => format!("{}[{}]", ty.abi_internal_type(), n),
+            _ => self.abi_type(),
+        }
+    }
+
+    /// Returns the component types of a tuple or of the innermost elements of arrays of tuples
+    fn abi_components(&self) -> Option<&[SolidityType]> {
+        match self {
+            SolidityType::Tuple(tys) | SolidityType::Struct(_, tys, _) => Some(tys),
+            SolidityType::DynamicArray(ty) | SolidityType::StaticArray(ty, _) => {
+                ty.abi_components()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the ABI JSON of a parameter of this type
+    fn to_abi_param_json(&self, name: &str) -> serde_json::Value {
+        let mut param = serde_json::json!({
+            "name": name,
+            "type": self.abi_type(),
+            "internalType": self.abi_internal_type(),
+        });
+        if let Some(tys) = self.abi_components() {
+            param["components"] = tys.iter().map(|ty| ty.to_abi_param_json("")).collect();
+        }
+        param
+    }
+
+    /// Parse a parameter from ABI JSON, where the components of a `tuple` type are given
+    /// separately and array suffixes follow the `tuple` keyword.
+    fn from_abi_param_json(param: &serde_json::Value) -> anyhow::Result<Self> {
+        let ty_str = param["type"]
+            .as_str()
+            .context("ABI parameter must have a string `type`")?;
+        let ty = match ty_str.strip_prefix("tuple") {
+            Some(suffix) => {
+                let components = param["components"]
+                    .as_array()
+                    .context("ABI parameter of type `tuple` must have `components`")?
+                    .iter()
+                    .map(SolidityType::from_abi_param_json)
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let tuple_str = SolidityType::Tuple(components).to_string();
+                SolidityType::parse(
+                    &format!("{}{}", tuple_str, suffix),
+                    &SignatureParseOptions::default(),
+                )
+            }
+            None => SolidityType::parse(ty_str, &SignatureParseOptions::default()),
+        }?;
+        Ok(ty)
+    }
+}
+
+impl SoliditySignature {
+    /// Returns the ABI JSON of this signature. Parameters without a name are given an empty one.
+    pub fn to_abi_json(&self) -> serde_json::Value {
+        let params_json = |params: &[(SolidityType, SignatureDataLocation)],
+                           names: &BTreeMap<usize, String>| {
+            params
+                .iter()
+                .enumerate()
+                .map(|(idx, (ty, _))| {
+                    ty.to_abi_param_json(names.get(&idx).map(String::as_str).unwrap_or(""))
+                })
+                .collect::<Vec<_>>()
+        };
+        match self.kind {
+            SignatureKind::Function => serde_json::json!({
+                "name": self.sig_name,
+                "type": "function",
+                "inputs": params_json(&self.para_types, &self.para_names),
+                "outputs": params_json(&self.ret_types, &self.ret_names),
+                "stateMutability": self.mutability.to_string(),
+            }),
+            SignatureKind::Event => {
+                let mut inputs = params_json(&self.para_types, &self.para_names);
+                for (idx, input) in inputs.iter_mut().enumerate() {
+                    input["indexed"] = serde_json::Value::Bool(self.indexed_params.contains(&idx));
+                }
+                serde_json::json!({
+                    "name": self.sig_name,
+                    "type": "event",
+                    "inputs": inputs,
+                    "anonymous": false,
+                })
+            }
+            SignatureKind::Error => serde_json::json!({
+                "name": self.sig_name,
+                "type": "error",
+                "inputs": params_json(&self.para_types, &self.para_names),
+            }),
+        }
+    }
+
+    /// Parse a signature from the ABI JSON of a function, event or error. All parameters are
+    /// placed in memory. Parameters of events are indexed if
+    /// their `indexed` field is true.
+    pub fn from_abi_json(json: &serde_json::Value) -> anyhow::Result<Self> {
+        let kind = match json.get("type") {
+            None => SignatureKind::Function,
+            Some(kind) if kind == "function" => SignatureKind::Function,
+            Some(kind) if kind == "event" => SignatureKind::Event,
+            Some(kind) if kind == "error" => SignatureKind::Error,
+            Some(kind) => {
+                return Err(anyhow!(
+                    "expected ABI JSON of a function, event or error, found `{}`",
+                    kind
+                ))
+            }
+        };
+        let sig_name = json["name"]
+            .as_str()
+            .context("ABI function must have a string `name`")?
+            .to_string();
+        let params = |key: &str| -> anyhow::Result<Vec<(SolidityType, SignatureDataLocation)>> {
+            match json.get(key) {
+                Some(params) => params
+                    .as_array()
+                    .with_context(|| format!("ABI function `{}` must be an array", key))?
+                    .iter()
+                    .map(|param| {
+                        SolidityType::from_abi_param_json(param)
+                            .map(|ty| (ty, SignatureDataLocation::Memory))
+                    })
+                    .collect(),
+                None => Ok(vec![]),
+            }
+        };
+        let mutability = match json.get("stateMutability").and_then(|m| m.as_str()) {
+            Some("pure") => StateMutability::Pure,
+            Some("view") => StateMutability::View,
+            Some("nonpayable") | None => StateMutability::NonPayable,
+            Some("payable") => StateMutability::Payable,
+            Some(other) => return Err(anyhow!("unknown state mutability `{}`", other)),
+        };
+        let names = |key: &str| -> BTreeMap<usize, String> {
+            match json.get(key).and_then(|params| params.as_array()) {
+                Some(params) => params
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, param)| match param["name"].as_str() {
+                        Some(name) if !name.is_empty() => Some((idx, name.to_string())),
+                        _ => None,
+                    })
+                    .collect(),
+                None => BTreeMap::new(),
+            }
+        };
+        let indexed_params = match json.get("inputs").and_then(|inputs| inputs.as_array()) {
+            Some(inputs) => inputs
+                .iter()
+                .enumerate()
+                .filter(|(_, input)| input["indexed"] == true)
+                .map(|(idx, _)| idx)
+                .collect(),
+            None => BTreeSet::new(),
+        };
+        let sig = SoliditySignature {
+            sig_name,
+            para_types: params("inputs")?,
+            ret_types: params("outputs")?,
+            mutability,
+            kind,
+            indexed_params,
+            para_names: names("inputs"),
+            ret_names: names("outputs"),
+        };
+        sig.check_sig_name()?;
+        sig.check_indexed_params()?;
+        Ok(sig)
+    }
+
+    /// Check whether two signatures describe the same function in the ABI, that is, whether
+    /// they agree on the kind, the name, the parameter and return types, the indexed
+    /// parameters and the state mutability. Data locations are not part of the ABI and are
+    /// ignored.
+    pub fn abi_equivalent(&self, other: &SoliditySignature) -> bool {
+        self.kind == other.kind
+            && self.sig_name == other.sig_name
+            && self.indexed_params == other.indexed_params
+            && self.mutability == other.mutability
+            && self.shape_eq(other)
+    }
+}
+
 fn check_simple_type_prefix(ty_str: &str) -> bool {
     /// Prefixes of value, bytes and string related types
     const SIMPLE_TYPE_PREFIX: &[&str] = &[
-        "uint", "int", "ufixed", "fixed", "bool", "address", "bytes", "string",
+        "uint", "int", "ufixed", "fixed", "bool", "address", "byte", "string",
     ];
     for prefix in SIMPLE_TYPE_PREFIX {
         if ty_str.starts_with(prefix) {
@@ -641,18 +2705,404 @@ fn check_simple_type_prefix(ty_str: &str) -> bool {
     false
 }
 
+/// Returns a warning for each field of a struct flattened into a tuple which is declared as
+/// internal, in the order of the fields.
+pub(crate) fn exposed_field_warnings(
+    struct_name: &str,
+    field_names: &[String],
+    internal_fields: &BTreeSet<String>,
+) -> Vec<String> {
+    field_names
+        .iter()
+        .filter(|name| internal_fields.contains(*name))
+        .map(|name| {
+            format!(
+                "struct `{}` exposes field `{}` in the ABI",
+                struct_name, name
+            )
+        })
+        .collect()
+}
+
+/// Error for content after the parameter list which is neither a modifier nor a returns clause,
+/// or which follows the returns clause
+fn unexpected_trailing_tokens(trailing: &str) -> anyhow::Error {
+    anyhow!("unexpected trailing tokens after signature: `{}`", trailing)
+}
+
+/// Strip a trailing parameter name from a parameter, returning the rest and the name. The name
+/// must be an identifier separated from the type by whitespace, and keywords which may follow
+/// a type, like `payable` in `address payable`, and type names are not names.
+fn strip_param_name(para: &str) -> Option<(&str, &str)> {
+    const KEYWORDS: &[&str] = &["payable", "memory", "calldata", "storage", "indexed"];
+    static NAME_REG: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"\s(?P<name>[a-zA-Z_$][a-zA-Z_$0-9]*)$").unwrap());
+    let name = NAME_REG.captures(para)?.name("name")?;
+    let rest = para[..name.start()].trim_end();
+    let is_type = SolidityType::parse(name.as_str(), &SignatureParseOptions::default()).is_ok();
+    if rest.is_empty() || is_type || KEYWORDS.contains(&name.as_str()) {
+        return None;
+    }
+    Some((rest, name.as_str()))
+}
+
+/// Strip a trailing `indexed` keyword from a parameter, which must be separated from the type
+/// by whitespace.
+fn strip_indexed(para: &str) -> Option<&str> {
+    para.strip_suffix("indexed")
+        .filter(|ty_str| ty_str.ends_with(char::is_whitespace))
+        .map(|ty_str| ty_str.trim_end())
+}
+
+/// Strip a trailing data location keyword from a parameter. The keyword must be separated from
+/// the type by whitespace, unless the type ends with an array or tuple bracket.
+fn strip_location<'a>(para: &'a str, location: &str) -> anyhow::Result<Option<&'a str>> {
+    match para.strip_suffix(location) {
+        Some(ty_str) if ty_str.ends_with(|c: char| c.is_whitespace() || c == ']' || c == ')') => {
+            Ok(Some(ty_str))
+        }
+        Some(_) => Err(anyhow!(
+            "data location `{}` must be separated from the type in `{}`",
+            location,
+            para
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Returns the byte offset of `inner` in `outer`, where `inner` must be a subslice of `outer`
+fn substr_offset(outer: &str, inner: &str) -> usize {
+    let offset = inner.as_ptr() as usize - outer.as_ptr() as usize;
+    debug_assert!(offset + inner.len() <= outer.len());
+    offset
+}
+
+/// Strip a leading word from `s` if it is followed by whitespace or the end of `s`
+fn strip_word<'a>(s: &'a str, word: &str) -> Option<&'a str> {
+    s.strip_prefix(word)
+        .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+/// Strip a leading visibility which is allowed for a callable function
+fn strip_visibility(s: &str) -> Option<&str> {
+    strip_word(s, "external").or_else(|| strip_word(s, "public"))
+}
+
+/// Check that each `[` in an array type is closed by a `]` before the next `[`
+fn check_array_brackets(ty_str: &str) -> bool {
+    let mut open = false;
+    for c in ty_str.chars() {
+        match c {
+            '[' if !open => open = true,
+            ']' if open => open = false,
+            '[' | ']' => return false,
+            _ => {}
+        }
+    }
+    !open
+}
+
+/// Find the position of the parenthesis closing the one at `open_pos`
+fn find_matching_paren(s: &str, open_pos: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (pos, c) in s.char_indices().skip_while(|(pos, _)| *pos < open_pos) {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(pos);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split a type list on the commas which are not nested inside a tuple
+fn split_top_level_commas(s: &str) -> anyhow::Result<Vec<&str>> {
+    let mut items = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (pos, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                if depth == 0 {
+                    return Err(anyhow!(PARSE_ERR_MSG));
+                }
+                depth -= 1;
+            }
+            ',' if depth == 0 => {
+                items.push(&s[start..pos]);
+                start = pos + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err(anyhow!(PARSE_ERR_MSG));
+    }
+    items.push(&s[start..]);
+    Ok(items)
+}
+
 fn check_type_int_range(num: usize) -> bool {
     (8..=256).contains(&num) && num % 8 == 0
 }
 
+/// Check the number of decimal places `N` of `fixed<M>x<N>` and `ufixed<M>x<N>`, for which the ABI
+/// requires `0 < N <= 80`. Any such `N` is allowed with any valid `M`, even if the resolution
+/// `10^-N` is finer than `M` bits can meaningfully use, e.g. `fixed8x80`.
 fn check_fixed_n_range(num: usize) -> bool {
-    num <= 80
+    (1..=80).contains(&num)
 }
 
 fn check_static_bytes_range(num: usize) -> bool {
     (1..=32).contains(&num)
 }
 
+/// Returns the index pairs of signatures with equal selectors. Signatures sharing a name but
+/// not their parameter types have distinct selectors unless the hashes collide.
+pub fn selector_collisions(sigs: &[SoliditySignature]) -> Vec<(usize, usize)> {
+    let mut seen: BTreeMap<[u8; 4], usize> = BTreeMap::new();
+    let mut collisions = vec![];
+    for (i, sig) in sigs.iter().enumerate() {
+        let selector = sig.selector();
+        if let Some(first) = seen.get(&selector) {
+            collisions.push((*first, i));
+        } else {
+            seen.insert(selector, i);
+        }
+    }
+    collisions
+}
+
+/// Strip a numeric suffix like `_2` from the name of a Move function which implements an
+/// overload of a Solidity function, e.g. `transfer_2` becomes `transfer`. Names consisting only
+/// of the suffix are kept.
+pub fn strip_overload_suffix(name: &str) -> &str {
+    match name.rfind('_') {
+        Some(pos)
+            if pos > 0
+                && pos + 1 < name.len()
+                && name[pos + 1..].chars().all(|c| c.is_ascii_digit()) =>
+        {
+            &name[..pos]
+        }
+        _ => name,
+    }
+}
+
+/// Returns a map from the canonical signatures to their selectors in hex, e.g. to document the
+/// functions of an interface.
+pub fn interface_manifest(sigs: &[SoliditySignature]) -> BTreeMap<String, String> {
+    sigs.iter()
+        .map(|sig| (sig.to_string(), sig.selector_hex()))
+        .collect()
+}
+
+/// Returns the selectors of the signatures together with their function names, sorted by
+/// selector and without duplicates. This is a compact alternative to a full ABI.
+pub fn selector_table(sigs: &[SoliditySignature]) -> Vec<([u8; 4], String)> {
+    sigs.iter()
+        .map(|sig| (sig.selector(), sig.sig_name.clone()))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Returns a keccak-256 hash of the sorted identifiers of the functions and events, i.e. their
+/// selectors and topics, which identifies the interface independent of the order of its entries.
+pub fn abi_fingerprint(sigs: &[SoliditySignature], events: &[SoliditySignature]) -> [u8; 32] {
+    let identifiers = sigs
+        .iter()
+        .chain(events.iter())
+        .map(|sig| sig.identifier())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    Keccak.hash(&identifiers)
+}
+
+/// The ABI of a callable function, as computed by `module_abi_report`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallableAbiReport {
+    /// The name of the Move function
+    pub function: String,
+    /// The canonical signature, if it could be parsed or derived from the Move function
+    pub signature: Option<String>,
+    /// The selector of the signature in hex
+    pub selector: Option<String>,
+    /// Whether the signature is compatible with the Move function
+    pub compatible: bool,
+    /// Why the signature could not be resolved or is not compatible
+    pub explanation: Option<String>,
+}
+
+/// A machine-readable report of the ABI of the callable functions of a module, e.g. for
+/// comparing it between revisions in CI.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleAbiReport {
+    pub callables: Vec<CallableAbiReport>,
+}
+
+/// Resolve the signatures of the callable functions among `funs` and check their compatibility
+/// like the dispatcher does, collecting the results instead of reporting diagnostics.
+pub fn module_abi_report(ctx: &Context, funs: &[FunctionEnv<'_>]) -> ModuleAbiReport {
+    let resolve = |fun: &FunctionEnv<'_>| match attributes::extract_callable_signature(fun) {
+        Some(sig_str) => {
+            SoliditySignature::parse_with_options(&sig_str, ctx.signature_parse_options())
+        }
+        None => SoliditySignature::try_create_default_solidity_signature(ctx, fun),
+    };
+    let callables = funs
+        .iter()
+        .filter(|fun| attributes::is_callable_fun(fun))
+        .map(|fun| {
+            let function = fun.symbol_pool().string(fun.get_name()).to_string();
+            match resolve(fun) {
+                Ok(sig) => {
+                    let explanation = match sig.check_move_representable() {
+                        Err(msg) => Some(msg.to_string()),
+                        Ok(()) if !sig.check_sig_compatibility(ctx, fun) => Some(
+                            "solidity signature is not compatible with the move signature"
+                                .to_string(),
+                        ),
+                        Ok(()) => None,
+                    };
+                    CallableAbiReport {
+                        function,
+                        signature: Some(sig.to_string()),
+                        selector: Some(sig.selector_hex()),
+                        compatible: explanation.is_none(),
+                        explanation,
+                    }
+                }
+                Err(msg) => CallableAbiReport {
+                    function,
+                    signature: None,
+                    selector: None,
+                    compatible: false,
+                    explanation: Some(msg.to_string()),
+                },
+            }
+        })
+        .collect();
+    ModuleAbiReport { callables }
+}
+
+/// Parse one signature per line of a block, skipping blank lines and lines starting with `//`.
+/// Each signature is parsed on its own, so a malformed line doesn't affect the others.
+pub fn parse_signature_block(s: &str) -> Vec<anyhow::Result<SoliditySignature>> {
+    s.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+        .map(SoliditySignature::parse_into_solidity_signature)
+        .collect()
+}
+
+/// Returns the Rust tuple type of the given types, with a trailing comma for a single element
+fn rust_tuple_type<'a>(tys: impl Iterator<Item = &'a SolidityType>) -> String {
+    let elems = tys.map(|ty| ty.rust_type()).collect::<Vec<_>>();
+    if elems.len() == 1 {
+        format!("({},)", elems[0])
+    } else {
+        format!("({})", elems.join(", "))
+    }
+}
+
+/// Selectors of well-known functions of the ERC-20, ERC-721 and ERC-165 interfaces.
+pub const KNOWN_SELECTORS: &[([u8; 4], &str)] = &[
+    ([0x06, 0xfd, 0xde, 0x03], "name()"),
+    ([0x95, 0xd8, 0x9b, 0x41], "symbol()"),
+    ([0x31, 0x3c, 0xe5, 0x67], "decimals()"),
+    ([0x18, 0x16, 0x0d, 0xdd], "totalSupply()"),
+    ([0x70, 0xa0, 0x82, 0x31], "balanceOf(address)"),
+    ([0xa9, 0x05, 0x9c, 0xbb], "transfer(address,uint256)"),
+    ([0xdd, 0x62, 0xed, 0x3e], "allowance(address,address)"),
+    ([0x09, 0x5e, 0xa7, 0xb3], "approve(address,uint256)"),
+    (
+        [0x23, 0xb8, 0x72, 0xdd],
+        "transferFrom(address,address,uint256)",
+    ),
+    ([0x63, 0x52, 0x21, 0x1e], "ownerOf(uint256)"),
+    (
+        [0x42, 0x84, 0x2e, 0x0e],
+        "safeTransferFrom(address,address,uint256)",
+    ),
+    (
+        [0xb8, 0x8d, 0x4f, 0xde],
+        "safeTransferFrom(address,address,uint256,bytes)",
+    ),
+    ([0xa2, 0x2c, 0xb4, 0x65], "setApprovalForAll(address,bool)"),
+    ([0x08, 0x18, 0x12, 0xfc], "getApproved(uint256)"),
+    (
+        [0xe9, 0x85, 0xe9, 0xc5],
+        "isApprovedForAll(address,address)",
+    ),
+    ([0x01, 0xff, 0xc9, 0xa7], "supportsInterface(bytes4)"),
+];
+
+/// A keccak-256 implementation, used to compute selectors.
+pub trait Keccak256 {
+    fn hash(&self, input: &[u8]) -> [u8; 32];
+}
+
+/// The default keccak-256 implementation, backed by the `sha3` crate.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Keccak;
+
+impl Keccak256 for Keccak {
+    fn hash(&self, input: &[u8]) -> [u8; 32] {
+        sha3::Keccak256::digest(input).into()
+    }
+}
+
+/// Compute the four-byte function selector of a canonical signature string such as
+/// `transfer(address,uint256)`. Like `keccak256` in Solidity, this hashes any string as is and
+/// does not validate that `canonical` is a well-formed signature.
+pub fn selector_of(canonical: &str) -> [u8; 4] {
+    selector_of_with(&Keccak, canonical)
+}
+
+/// Compute the function selector of a canonical signature string with the given hasher.
+pub fn selector_of_with(hasher: &impl Keccak256, canonical: &str) -> [u8; 4] {
+    let hash = hasher.hash(canonical.as_bytes());
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(&hash[..4]);
+    selector
+}
+
+/// Parse the declaration of a user-defined value type of the form `Name=type`, where `type` is
+/// the underlying primitive type, e.g. `Price=uint256`.
+pub fn parse_value_type(decl: &str) -> anyhow::Result<(String, SolidityPrimitiveType)> {
+    static NAME_REG: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z_$][a-zA-Z_$0-9]*$").unwrap());
+    let (name, ty_str) = decl
+        .split_once('=')
+        .with_context(|| format!("expected `Name=type` for a value type, found `{}`", decl))?;
+    let (name, ty_str) = (name.trim(), ty_str.trim());
+    if !NAME_REG.is_match(name) || name.parse::<SolidityType>().is_ok() {
+        return Err(anyhow!("illegal name of a value type `{}`", name));
+    }
+    match ty_str.parse::<SolidityType>()? {
+        SolidityType::Primitive(p) => Ok((name.to_string(), p)),
+        _ => Err(anyhow!(
+            "the underlying type of value type `{}` must be a primitive type, found `{}`",
+            name,
+            ty_str
+        )),
+    }
+}
+
+/// Compute the function selector of the function `name` with the given parameter types, without
+/// constructing a full signature.
+pub fn selector_from_parts(name: &str, params: &[SolidityType]) -> [u8; 4] {
+    selector_of(&format!("{}({})", name, params.iter().join(",")))
+}
+
 /// Mangle a slice of solidity types.
 pub(crate) fn mangle_solidity_types(tys: &[SolidityType]) -> String {
     if tys.is_empty() {
@@ -0,0 +1,2428 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    context::Context,
+    generator::Generator,
+    solidity_ty::{
+        abi_fingerprint, exposed_field_warnings, interface_manifest, module_abi_report,
+        parse_signature_block, parse_value_type, selector_collisions, selector_from_parts,
+        selector_of, selector_of_with, selector_table, strip_overload_suffix, AccessKind,
+        FunctionRole, Keccak, Keccak256, ModuleAbiReport, SignatureDataLocation,
+        SignatureErrorSpan, SignatureKind, SignatureParseOptions, SolidityPragma,
+        SolidityPrimitiveType, SoliditySignature, SoliditySignatureBuilder, SolidityType,
+        SolidityTypeError, SolidityTypeParseError, StateMutability, KNOWN_SELECTORS,
+    },
+    yul_functions::substitute_placeholders,
+    Options,
+};
+use codespan_reporting::{diagnostic::Severity, term::termcolor::Buffer};
+use move_model::{
+    model::GlobalEnv,
+    options::ModelBuilderOptions,
+    run_model_builder_with_options,
+    ty::{PrimitiveType, Type},
+};
+use std::collections::BTreeMap;
+
+/// Build a model from the source of a single Move module, asserting that it has no errors
+fn model_from_source(source: &str) -> GlobalEnv {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("M.move");
+    std::fs::write(&path, source).unwrap();
+    let env = run_model_builder_with_options(
+        &[path.to_string_lossy().to_string()],
+        &[],
+        ModelBuilderOptions::default(),
+        BTreeMap::new(),
+    )
+    .unwrap();
+    assert!(!env.has_errors());
+    env
+}
+
+/// Returns the diagnostics reported so far
+fn diagnostics(env: &GlobalEnv) -> String {
+    let mut writer = Buffer::no_color();
+    env.report_diag(&mut writer, Severity::Warning);
+    String::from_utf8_lossy(&writer.into_inner()).to_string()
+}
+
+fn parse_sig(sig_str: &str) -> SoliditySignature {
+    SoliditySignature::parse_into_solidity_signature(sig_str).unwrap()
+}
+
+fn parse_sig_err(sig_str: &str) -> String {
+    SoliditySignature::parse_into_solidity_signature(sig_str)
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn empty_tuple_parameter() {
+    assert_eq!(
+        parse_sig_err("foo(())"),
+        "empty tuple is not a valid parameter type"
+    );
+    let sig = parse_sig("foo() returns ()");
+    assert!(sig.para_types.is_empty());
+    assert!(sig.ret_types.is_empty());
+}
+
+#[test]
+fn selector_of_canonical_string() {
+    assert_eq!(
+        selector_of("transfer(address,uint256)"),
+        [0xa9, 0x05, 0x9c, 0xbb]
+    );
+}
+
+#[test]
+fn signed_and_unsigned_range() {
+    let range = |ty: SolidityPrimitiveType| {
+        let ty = SolidityType::Primitive(ty);
+        (
+            substitute_placeholders(&ty.min_value()).unwrap_or_else(|| ty.min_value()),
+            substitute_placeholders(&ty.max_value()).unwrap(),
+        )
+    };
+    assert_eq!(
+        range(SolidityPrimitiveType::Int(8, false)),
+        (
+            "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff80".to_string(),
+            "0x7f".to_string()
+        )
+    );
+    assert_eq!(
+        range(SolidityPrimitiveType::Int(256, false)),
+        (
+            "0x8000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            "0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff".to_string()
+        )
+    );
+    assert_eq!(
+        range(SolidityPrimitiveType::Uint(8, false)),
+        ("0".to_string(), "0xff".to_string())
+    );
+    assert_eq!(
+        range(SolidityPrimitiveType::Uint(256, false)),
+        (
+            "0".to_string(),
+            "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff".to_string()
+        )
+    );
+}
+
+#[test]
+fn accepts_value_only_when_payable() {
+    assert!(parse_sig("deposit() payable").accepts_value());
+    assert!(!parse_sig("balance() view returns (uint256)").accepts_value());
+    assert!(!parse_sig("withdraw(uint256)").accepts_value());
+}
+
+#[test]
+fn array_of_records() {
+    let sig = parse_sig("foo((uint64,bool)[] memory)");
+    assert_eq!(
+        sig.para_types[0].0,
+        SolidityType::DynamicArray(Box::new(SolidityType::Tuple(vec![
+            SolidityType::Primitive(SolidityPrimitiveType::Uint(64, false)),
+            SolidityType::Primitive(SolidityPrimitiveType::Bool),
+        ])))
+    );
+    assert_eq!(sig.to_string(), "foo((uint64,bool)[])");
+}
+
+#[test]
+fn default_reference_location() {
+    let options = SignatureParseOptions {
+        default_reference_location: SignatureDataLocation::CallData,
+        ..SignatureParseOptions::default()
+    };
+    let sig =
+        SoliditySignature::parse_with_options("f(bytes,uint8,string memory)", &options).unwrap();
+    assert_eq!(sig.para_types[0].1, SignatureDataLocation::CallData);
+    assert_eq!(sig.para_types[1].1, SignatureDataLocation::Memory);
+    assert_eq!(sig.para_types[2].1, SignatureDataLocation::Memory);
+    assert_eq!(
+        parse_sig("f(bytes)").para_types[0].1,
+        SignatureDataLocation::Memory
+    );
+    assert_eq!(sig.to_string(), "f(bytes,uint8,string)");
+}
+
+#[test]
+fn signature_head_errors() {
+    assert_eq!(parse_sig_err("add"), "missing opening parenthesis");
+    assert_eq!(parse_sig_err("  (uint8)"), "missing function name");
+    assert_eq!(
+        parse_sig_err("1add()"),
+        "function name cannot start with a digit"
+    );
+    assert_eq!(
+        parse_sig_err(" add) () "),
+        "function name contains invalid character `)` at position 4"
+    );
+    assert_eq!(
+        parse_sig_err("a-b(uint8)"),
+        "function name contains invalid character `-` at position 1"
+    );
+}
+
+#[test]
+fn string_against_byte_vector() {
+    let env = GlobalEnv::new();
+    let byte_vector = Type::Vector(Box::new(Type::Primitive(PrimitiveType::U8)));
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    assert!(SolidityType::SolidityString.check_type_compatibility(&ctx, &byte_vector));
+    let options = Options {
+        strict_strings: true,
+        ..Options::default()
+    };
+    let ctx = Context::new(&options, &env, false);
+    assert!(!SolidityType::SolidityString.check_type_compatibility(&ctx, &byte_vector));
+    assert!(SolidityType::Bytes.check_type_compatibility(&ctx, &byte_vector));
+}
+
+#[test]
+fn referenced_type_names() {
+    // Structs in tuple form are anonymous, so only primitives and tuples are referenced here.
+    let sig = parse_sig("f((uint8,bytes)[],string) returns ((bool,address))");
+    assert!(sig.referenced_type_names().is_empty());
+    // Named structs are referenced once each, also in arrays and return types
+    let options = SignatureParseOptions {
+        structs: vec![
+            (
+                "Order".to_string(),
+                vec![SolidityType::Primitive(SolidityPrimitiveType::Address(
+                    false,
+                ))],
+            ),
+            ("Fill".to_string(), vec![SolidityType::Bytes]),
+        ]
+        .into_iter()
+        .collect(),
+        ..SignatureParseOptions::default()
+    };
+    let sig =
+        SoliditySignature::parse_with_options("f(Order,Fill[]) returns (Order)", &options).unwrap();
+    assert_eq!(sig.referenced_type_names(), vec!["Fill", "Order"]);
+}
+
+#[test]
+fn ambiguous_default_translation() {
+    let env = GlobalEnv::new();
+    let byte_vector = Type::Vector(Box::new(Type::Primitive(PrimitiveType::U8)));
+    let u64_vector = Type::Vector(Box::new(Type::Primitive(PrimitiveType::U64)));
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    assert!(SolidityType::check_default_translation(&ctx, &byte_vector).is_ok());
+    let options = Options {
+        require_explicit_signatures_for_ambiguous: true,
+        ..Options::default()
+    };
+    let ctx = Context::new(&options, &env, false);
+    assert!(SolidityType::check_default_translation(&ctx, &byte_vector).is_err());
+    assert!(SolidityType::check_default_translation(&ctx, &u64_vector).is_ok());
+}
+
+#[test]
+fn stack_slot_estimate() {
+    let sig = parse_sig("f(uint8,bytes,uint64[3],(bool,address)) returns (string)");
+    assert_eq!(sig.estimated_stack_slots(), 8);
+    assert!(!sig.exceeds_stack_limit());
+    let sig = parse_sig("f(uint256[8],(uint8,bool)[4],bytes)");
+    assert_eq!(sig.estimated_stack_slots(), 17);
+    assert!(sig.exceeds_stack_limit());
+}
+
+#[test]
+fn getter_mutability() {
+    let total_supply = SoliditySignature::getter_signature(
+        "totalSupply",
+        SolidityType::Primitive(SolidityPrimitiveType::Uint(256, false)),
+        false,
+    );
+    assert_eq!(total_supply.mutability, StateMutability::View);
+    assert_eq!(total_supply.to_string(), "totalSupply()");
+    let decimals = SoliditySignature::getter_signature(
+        "decimals",
+        SolidityType::Primitive(SolidityPrimitiveType::Uint(8, false)),
+        true,
+    );
+    assert_eq!(decimals.mutability, StateMutability::Pure);
+    assert!(!decimals.accepts_value());
+}
+
+#[test]
+fn matches_selector_hex() {
+    let sig = parse_sig("transfer(address,uint256) returns (bool)");
+    assert!(sig.matches_selector_hex("0xa9059cbb").unwrap());
+    assert!(!sig.matches_selector_hex("0x23b872dd").unwrap());
+    assert!(sig.matches_selector_hex("a9059cbb").is_err());
+    assert!(sig.matches_selector_hex("0xa9059c").is_err());
+    assert!(sig.matches_selector_hex("0xa9059cbz").is_err());
+}
+
+#[test]
+fn rust_param_tuple() {
+    assert_eq!(
+        parse_sig("f(uint256,address,bytes)").rust_param_tuple(),
+        "(U256, Address, Vec<u8>)"
+    );
+    assert_eq!(
+        parse_sig("f(uint64[] memory,(bool,int24),bytes32[2])").rust_param_tuple(),
+        "(Vec<u64>, (bool, I256), [[u8; 32]; 2])"
+    );
+    assert_eq!(parse_sig("f(string)").rust_param_tuple(), "(String,)");
+    assert_eq!(parse_sig("f()").rust_param_tuple(), "()");
+}
+
+#[test]
+fn empty_sig_name() {
+    let mut sig = parse_sig("f(uint8)");
+    assert!(sig.check_sig_name().is_ok());
+    sig.sig_name = String::new();
+    assert_eq!(
+        sig.check_sig_name().unwrap_err().to_string(),
+        "the function name of a signature must not be empty"
+    );
+}
+
+#[test]
+fn overloads_and_selector_collisions() {
+    let sigs = vec![
+        parse_sig("f(uint8)"),
+        parse_sig("f(uint64)"),
+        parse_sig("f()"),
+        parse_sig("f(uint8) returns (bool)"),
+    ];
+    assert_eq!(selector_collisions(&sigs[..3]), vec![]);
+    assert_eq!(selector_collisions(&sigs), vec![(0, 3)]);
+}
+
+#[test]
+fn selector_with_custom_hasher() {
+    // Returns the length of the input followed by its first bytes.
+    struct MockHasher;
+    impl Keccak256 for MockHasher {
+        fn hash(&self, input: &[u8]) -> [u8; 32] {
+            let mut hash = [0u8; 32];
+            hash[0] = input.len() as u8;
+            hash[1..4].copy_from_slice(&input[..3]);
+            hash
+        }
+    }
+    assert_eq!(
+        selector_of_with(&MockHasher, "foo(uint8)"),
+        [10, b'f', b'o', b'o']
+    );
+    assert_eq!(
+        selector_of_with(&Keccak, "foo(uint8)"),
+        selector_of("foo(uint8)")
+    );
+}
+
+#[test]
+fn array_element_is_dynamic() {
+    let para_types = parse_sig("f(uint256[],bytes[],uint256,(uint8,string)[2])").para_types;
+    assert_eq!(para_types[0].0.array_element_is_dynamic(), Some(false));
+    assert_eq!(para_types[1].0.array_element_is_dynamic(), Some(true));
+    assert_eq!(para_types[2].0.array_element_is_dynamic(), None);
+    assert_eq!(para_types[3].0.array_element_is_dynamic(), Some(true));
+}
+
+#[test]
+fn modifiers_precede_returns() {
+    let sig = parse_sig("totalSupply() external view returns(uint256)");
+    assert_eq!(sig.mutability, StateMutability::View);
+    assert_eq!(sig.ret_types.len(), 1);
+    assert_eq!(
+        parse_sig("f() public payable").mutability,
+        StateMutability::Payable
+    );
+    assert_eq!(
+        parse_sig_err("totalSupply() returns(uint256) view"),
+        "state mutability must precede `returns`"
+    );
+    assert_eq!(
+        parse_sig_err("f() view pure"),
+        "state mutability is specified more than once"
+    );
+}
+
+#[test]
+fn sorted_selector_table() {
+    let sigs = vec![
+        parse_sig("transfer(address,uint256) returns (bool)"),
+        parse_sig("balanceOf(address) view returns (uint256)"),
+        parse_sig("approve(address,uint256) returns (bool)"),
+        parse_sig("balanceOf(address)"),
+    ];
+    assert_eq!(
+        selector_table(&sigs),
+        vec![
+            ([0x09, 0x5e, 0xa7, 0xb3], "approve".to_string()),
+            ([0x70, 0xa0, 0x82, 0x31], "balanceOf".to_string()),
+            ([0xa9, 0x05, 0x9c, 0xbb], "transfer".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn spec_types_cannot_be_translated() {
+    let env = GlobalEnv::new();
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    for (ty, name) in [
+        (PrimitiveType::Num, "Num"),
+        (PrimitiveType::Range, "Range"),
+        (PrimitiveType::EventStore, "EventStore"),
+    ] {
+        let err = SolidityType::try_translate_from_move(&ctx, &Type::Primitive(ty)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "internal spec type `{}` cannot be exposed in the ABI; this usually indicates \
+                 a specification expression leaked into a callable",
+                name
+            )
+        );
+    }
+    let nested = Type::Vector(Box::new(Type::Primitive(PrimitiveType::Num)));
+    assert!(SolidityType::try_translate_from_move(&ctx, &nested).is_err());
+}
+
+#[test]
+fn max_value_bytes() {
+    let max_bytes = |ty: SolidityPrimitiveType| {
+        SolidityType::Primitive(ty)
+            .max_value_bytes()
+            .map(hex::encode)
+    };
+    let expected =
+        |leading_zeros: usize, rest: &str| Some(format!("{}{}", "00".repeat(leading_zeros), rest));
+    assert_eq!(
+        max_bytes(SolidityPrimitiveType::Uint(8, false)),
+        expected(31, "ff")
+    );
+    assert_eq!(max_bytes(SolidityPrimitiveType::Bool), expected(31, "01"));
+    assert_eq!(
+        max_bytes(SolidityPrimitiveType::Address(false)),
+        expected(12, &"ff".repeat(20))
+    );
+    assert_eq!(
+        max_bytes(SolidityPrimitiveType::Int(16, false)),
+        expected(30, "7fff")
+    );
+    assert_eq!(
+        SolidityType::BytesStatic(2)
+            .max_value_bytes()
+            .map(hex::encode),
+        Some(format!("ffff{}", "00".repeat(30)))
+    );
+    assert_eq!(SolidityType::Bytes.max_value_bytes(), None);
+}
+
+#[test]
+fn byte_alias() {
+    let sig = parse_sig("foo(byte,byte[2])");
+    assert_eq!(sig.para_types[0].0, SolidityType::BytesStatic(1));
+    assert_eq!(sig.to_string(), "foo(bytes1,bytes1[2])");
+    assert_eq!(
+        sig.selector_hex(),
+        parse_sig("foo(bytes1,bytes1[2])").selector_hex()
+    );
+    assert!(SoliditySignature::parse_into_solidity_signature("foo(bytex)").is_err());
+}
+
+#[test]
+fn normalize_integer_aliases() {
+    let para_types = parse_sig("f((uint, uint[]),((uint256)),int[2])").para_types;
+    assert_eq!(
+        para_types[0].0.normalize().to_string(),
+        "(uint256,uint256[])"
+    );
+    assert_eq!(para_types[1].0.normalize().to_string(), "((uint256))");
+    assert_eq!(para_types[2].0.normalize().to_string(), "int256[2]");
+    assert_eq!(
+        para_types[0].0.normalize(),
+        parse_sig("f((uint256,uint256[]))").para_types[0].0
+    );
+}
+
+#[test]
+fn abi_json_internal_type() {
+    let sig = parse_sig("f((uint8,bool)[2],address) view returns (uint256)");
+    assert_eq!(
+        sig.to_abi_json(),
+        serde_json::json!({
+            "name": "f",
+            "type": "function",
+            "inputs": [
+                {
+                    "name": "",
+                    "type": "tuple[2]",
+                    "internalType": "tuple[2]",
+                    "components": [
+                        {"name": "", "type": "uint8", "internalType": "uint8"},
+                        {"name": "", "type": "bool", "internalType": "bool"},
+                    ],
+                },
+                {"name": "", "type": "address", "internalType": "address"},
+            ],
+            "outputs": [{"name": "", "type": "uint256", "internalType": "uint256"}],
+            "stateMutability": "view",
+        })
+    );
+}
+
+#[test]
+fn shape_eq_ignores_names() {
+    let transfer = parse_sig("transfer(address,uint256) returns (bool)");
+    let send = parse_sig("send(address, uint) returns (bool)");
+    assert!(transfer.shape_eq(&send));
+    assert_ne!(
+        selector_of(&transfer.to_string()),
+        selector_of(&send.to_string())
+    );
+    assert!(!transfer.shape_eq(&parse_sig("transfer(address,uint256)")));
+    assert!(parse_sig("f(bytes memory)").shape_eq(&parse_sig("g(bytes)")));
+}
+
+#[test]
+fn requires_abi_v2() {
+    assert!(!parse_sig("f(uint256[],bytes,string,address[2]) returns (bytes32)").requires_abi_v2());
+    assert!(parse_sig("f((uint8,bool))").requires_abi_v2());
+    assert!(parse_sig("f() returns ((uint8,bool)[])").requires_abi_v2());
+    assert!(parse_sig("f(uint8[][])").requires_abi_v2());
+    assert!(parse_sig("f(string[2])").requires_abi_v2());
+}
+
+#[test]
+fn expected_move_params() {
+    let env = GlobalEnv::new();
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    let sig = parse_sig("f(uint64,address,bytes,uint256,uint32[])");
+    let expected = sig.expected_move_params(&ctx);
+    assert_eq!(
+        expected,
+        vec![
+            Some(Type::Primitive(PrimitiveType::U64)),
+            Some(Type::Primitive(PrimitiveType::Address)),
+            Some(Type::Vector(Box::new(Type::Primitive(PrimitiveType::U8)))),
+            None,
+            Some(Type::Vector(Box::new(Type::Primitive(PrimitiveType::U64)))),
+        ]
+    );
+    for ((ty, _), move_ty) in sig.para_types.iter().zip(expected.iter()) {
+        if let Some(move_ty) = move_ty {
+            assert!(ty.check_type_compatibility(&ctx, move_ty));
+        }
+    }
+}
+
+#[test]
+fn worst_case_calldata_gas() {
+    let transfer = parse_sig("transfer(address,uint256) returns (bool)");
+    assert_eq!(transfer.exact_calldata_size(&[]).unwrap(), 64);
+    assert_eq!(transfer.worst_case_calldata_gas(&[]).unwrap(), 68 * 16);
+    let sig = parse_sig("f(bytes,uint8,uint64[])");
+    assert_eq!(
+        sig.exact_calldata_size(&[(0, 33), (2, 3)]).unwrap(),
+        96 + (32 + 64) + (32 + 96)
+    );
+    assert!(sig.exact_calldata_size(&[(0, 33)]).is_err());
+    assert!(sig.exact_calldata_size(&[(0, 33), (1, 1), (2, 3)]).is_err());
+    assert!(parse_sig("f(string[])")
+        .exact_calldata_size(&[(0, 1)])
+        .is_err());
+}
+
+#[test]
+fn fixed_point_grid() {
+    for ty in ["fixed8x1", "fixed256x80", "ufixed8x80", "fixed128x18"] {
+        assert_eq!(
+            parse_sig(&format!("f({})", ty)).to_string(),
+            format!("f({})", ty)
+        );
+    }
+    for ty in [
+        "fixed8x0",
+        "ufixed256x81",
+        "fixed12x10",
+        "fixed264x1",
+        "fixed0x1",
+    ] {
+        assert!(SoliditySignature::parse_into_solidity_signature(&format!("f({})", ty)).is_err());
+    }
+}
+
+#[test]
+fn signature_builder() {
+    let uint = |n| SolidityType::Primitive(SolidityPrimitiveType::Uint(n, false));
+    let sig = SoliditySignatureBuilder::new()
+        .name("f")
+        .param(uint(8), SignatureDataLocation::Memory)
+        .param(SolidityType::Bytes, SignatureDataLocation::CallData)
+        .ret(SolidityType::Tuple(vec![uint(256)]))
+        .mutability(StateMutability::View)
+        .build()
+        .unwrap();
+    assert_eq!(sig.to_string(), "f(uint8,bytes)");
+    assert_eq!(sig.para_types[1].1, SignatureDataLocation::CallData);
+    assert_eq!(sig.mutability, StateMutability::View);
+
+    let err = |builder: SoliditySignatureBuilder| builder.build().unwrap_err().to_string();
+    assert_eq!(
+        err(SoliditySignatureBuilder::new().param(uint(8), SignatureDataLocation::Memory)),
+        "the function name of a signature must not be empty"
+    );
+    assert_eq!(
+        err(SoliditySignatureBuilder::new().name("f").ret(uint(7))),
+        "`uint7` is not a valid ABI type"
+    );
+    assert_eq!(
+        err(SoliditySignatureBuilder::new()
+            .name("f")
+            .param(uint(8), SignatureDataLocation::CallData)),
+        "data location can only be specified for array or struct types"
+    );
+}
+
+#[test]
+fn word_aligned_types() {
+    let para_types = parse_sig("f(bool,uint8,uint256,(int256,uint256[2]),(uint128,uint128),bytes)")
+        .para_types
+        .into_iter()
+        .map(|(ty, _)| ty)
+        .collect::<Vec<_>>();
+    let aligned = |padded| {
+        para_types
+            .iter()
+            .map(|ty| ty.is_word_aligned(padded))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(aligned(true), vec![true, true, true, true, true, false]);
+    assert_eq!(aligned(false), vec![false, false, true, true, false, false]);
+}
+
+#[test]
+fn trailing_tokens() {
+    assert_eq!(
+        parse_sig_err("foo() JUNK"),
+        "unexpected trailing tokens after signature: `JUNK`"
+    );
+    assert_eq!(
+        parse_sig_err("foo() view returns(uint256) EXTRA"),
+        "unexpected trailing tokens after signature: `EXTRA`"
+    );
+    assert_eq!(
+        parse_sig_err("foo() returns (uint8) (bool)"),
+        "unexpected trailing tokens after signature: `(bool)`"
+    );
+    let sig = parse_sig("foo(uint8) external payable returns ((uint8,bool))");
+    assert_eq!(sig.mutability, StateMutability::Payable);
+    assert_eq!(sig.ret_types.len(), 1);
+}
+
+#[test]
+fn calldata_access_kind() {
+    let uint256 = SolidityType::Primitive(SolidityPrimitiveType::Uint(256, false));
+    let calldata = SignatureDataLocation::CallData;
+    assert_eq!(uint256.access_kind(&calldata), AccessKind::InPlaceCalldata);
+    assert_eq!(
+        SolidityType::StaticArray(Box::new(uint256.clone()), 3).access_kind(&calldata),
+        AccessKind::InPlaceCalldata
+    );
+    assert_eq!(
+        SolidityType::Bytes.access_kind(&calldata),
+        AccessKind::CopyToMemory
+    );
+    assert_eq!(
+        uint256.access_kind(&SignatureDataLocation::Memory),
+        AccessKind::CopyToMemory
+    );
+}
+
+#[test]
+fn whitespace_before_parameters() {
+    for sig_str in [
+        "transfer (address)",
+        "transfer\t(address)",
+        "transfer\n(address)",
+        " \r\ntransfer \t\n (address) ",
+    ] {
+        let sig = parse_sig(sig_str);
+        assert_eq!(sig.sig_name, "transfer");
+        assert_eq!(sig.to_string(), "transfer(address)");
+    }
+}
+
+#[test]
+fn address_as_bytes32() {
+    let env = GlobalEnv::new();
+    let address = Type::Primitive(PrimitiveType::Address);
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    assert!(!SolidityType::BytesStatic(32).check_type_compatibility(&ctx, &address));
+    let options = Options {
+        allow_address_as_bytes32: true,
+        ..Options::default()
+    };
+    let ctx = Context::new(&options, &env, false);
+    assert!(SolidityType::BytesStatic(32).check_type_compatibility(&ctx, &address));
+    assert!(SolidityType::BytesStatic(32)
+        .check_type_compatibility(&ctx, &Type::Primitive(PrimitiveType::Signer)));
+    assert!(!SolidityType::BytesStatic(20).check_type_compatibility(&ctx, &address));
+}
+
+#[test]
+fn interface_manifest_is_sorted() {
+    let sigs = vec![
+        parse_sig("transfer(address,uint256) returns (bool)"),
+        parse_sig("approve(address, uint) returns (bool)"),
+        parse_sig("allowance(address,address) view returns (uint256)"),
+    ];
+    assert_eq!(
+        interface_manifest(&sigs).into_iter().collect::<Vec<_>>(),
+        vec![
+            (
+                "allowance(address,address)".to_string(),
+                "0xdd62ed3e".to_string()
+            ),
+            (
+                "approve(address,uint256)".to_string(),
+                "0x095ea7b3".to_string()
+            ),
+            (
+                "transfer(address,uint256)".to_string(),
+                "0xa9059cbb".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn unbalanced_array_brackets() {
+    for ty in [
+        "uint256[2",
+        "uint256]2",
+        "uint256]2[",
+        "uint256[[2]]",
+        "uint256[2]]",
+    ] {
+        assert_eq!(
+            parse_sig_err(&format!("f({})", ty)),
+            format!("unbalanced brackets in type `{}`", ty)
+        );
+    }
+    assert_eq!(
+        parse_sig("f((uint8[2],bool)[][3])").to_string(),
+        "f((uint8[2],bool)[][3])"
+    );
+}
+
+#[test]
+fn param_and_return_type_strings() {
+    let sig = parse_sig("transfer(address, uint) returns (bool)");
+    assert_eq!(sig.param_type_strings(), vec!["address", "uint256"]);
+    assert_eq!(sig.return_type_strings(), vec!["bool"]);
+    let sig = parse_sig("f((uint8,bytes)[] memory)");
+    assert_eq!(sig.param_type_strings(), vec!["(uint8,bytes)[]"]);
+    assert!(sig.return_type_strings().is_empty());
+}
+
+#[test]
+fn downgrade_to_abi_v1() {
+    let sig = parse_sig("f((uint8,bytes) memory,bool) returns ((address,uint256))");
+    let downgraded = sig.downgrade_to_v1().unwrap();
+    assert_eq!(downgraded.to_string(), "f(uint8,bytes,bool)");
+    assert_eq!(downgraded.return_type_strings(), vec!["address", "uint256"]);
+    assert!(!downgraded.requires_abi_v2());
+    assert_eq!(
+        parse_sig("g(uint8[])")
+            .downgrade_to_v1()
+            .unwrap()
+            .to_string(),
+        "g(uint8[])"
+    );
+    assert!(parse_sig("f((uint8,(bool,bool)))")
+        .downgrade_to_v1()
+        .is_none());
+    assert!(parse_sig("f((uint8,bool)[])").downgrade_to_v1().is_none());
+}
+
+#[test]
+fn pad_to_word() {
+    let padded = |ty: SolidityType, raw: &[u8]| ty.pad_to_word(raw).map(hex::encode);
+    assert_eq!(
+        padded(
+            SolidityType::Primitive(SolidityPrimitiveType::Uint(8, false)),
+            &[0xab]
+        )
+        .unwrap(),
+        format!("{}ab", "00".repeat(31))
+    );
+    assert_eq!(
+        padded(
+            SolidityType::Primitive(SolidityPrimitiveType::Address(false)),
+            &[0x11; 20]
+        )
+        .unwrap(),
+        format!("{}{}", "00".repeat(12), "11".repeat(20))
+    );
+    assert_eq!(
+        padded(SolidityType::BytesStatic(4), &[0xa9, 0x05, 0x9c, 0xbb]).unwrap(),
+        format!("a9059cbb{}", "00".repeat(28))
+    );
+    assert!(padded(SolidityType::BytesStatic(4), &[0; 5]).is_err());
+    assert!(padded(SolidityType::Bytes, &[]).is_err());
+}
+
+#[test]
+fn max_type_depth() {
+    let env = GlobalEnv::new();
+    let sig = "f(uint8[][],(bool,uint8[])[])";
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    assert!(SoliditySignature::parse_with_options(sig, ctx.signature_parse_options()).is_ok());
+    let options = Options {
+        max_type_depth: 1,
+        ..Options::default()
+    };
+    let ctx = Context::new(&options, &env, false);
+    let err = SoliditySignature::parse_with_options(sig, ctx.signature_parse_options())
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        err,
+        "type `uint8[][]` exceeds the maximum nesting depth of 1"
+    );
+    assert_eq!(parse_sig(sig).para_types[1].0.depth(), 3);
+}
+
+fn abi_json_round_trip(sig: &SoliditySignature) -> SoliditySignature {
+    SoliditySignature::from_abi_json(&sig.to_abi_json()).unwrap()
+}
+
+#[test]
+fn abi_json_round_trip_fixtures() {
+    for sig_str in [
+        "f(((uint8,bool),(address,bytes)[2]),string) returns ((uint256,(bytes32,int8)))",
+        "g((uint64,string)[],(bool,uint8[])[3][]) view returns ((address,bytes)[])",
+        "h() pure",
+    ] {
+        let sig = parse_sig(sig_str);
+        let round_trip = abi_json_round_trip(&sig);
+        assert!(sig.abi_equivalent(&round_trip), "{}", sig_str);
+        assert_eq!(round_trip.to_string(), sig.to_string());
+    }
+}
+
+#[test]
+fn abi_json_round_trip_generated() {
+    // Build types by wrapping simple types in arrays and tuples up to a fixed nesting depth
+    let mut tys = [
+        "uint8", "int256", "address", "bool", "bytes", "string", "bytes32",
+    ]
+    .iter()
+    .map(|ty| ty.to_string())
+    .collect::<Vec<_>>();
+    for _ in 0..2 {
+        let mut wrapped = vec![];
+        for (i, ty) in tys.iter().enumerate() {
+            wrapped.push(format!("{}[]", ty));
+            wrapped.push(format!("{}[2]", ty));
+            wrapped.push(format!("({},{})", ty, tys[(i + 1) % tys.len()]));
+        }
+        tys.extend(wrapped);
+    }
+    let mutabilities = [
+        StateMutability::Pure,
+        StateMutability::View,
+        StateMutability::NonPayable,
+        StateMutability::Payable,
+    ];
+    for (i, ty) in tys.iter().enumerate() {
+        let mut sig = parse_sig(&format!(
+            "f{}({},{}) returns ({})",
+            i,
+            ty,
+            tys[tys.len() - 1 - i],
+            ty
+        ));
+        sig.mutability = mutabilities[i % mutabilities.len()];
+        assert!(sig.abi_equivalent(&abi_json_round_trip(&sig)), "{}", sig);
+    }
+}
+
+#[test]
+fn from_abi_json_errors() {
+    let from_json = |json: serde_json::Value| SoliditySignature::from_abi_json(&json);
+    assert!(from_json(serde_json::json!({"type": "constructor", "inputs": []})).is_err());
+    assert!(from_json(serde_json::json!({"type": "function", "inputs": []})).is_err());
+    assert!(from_json(serde_json::json!({
+        "type": "function",
+        "name": "f",
+        "inputs": [{"name": "", "type": "tuple[]"}],
+    }))
+    .is_err());
+    let sig = from_json(serde_json::json!({"name": "f", "inputs": [{"type": "uint8"}]})).unwrap();
+    assert!(sig.abi_equivalent(&parse_sig("f(uint8)")));
+    assert!(!sig.abi_equivalent(&parse_sig("f(uint8) view")));
+}
+
+#[test]
+fn checked_int_construction() {
+    assert_eq!(
+        SolidityPrimitiveType::uint(64).unwrap(),
+        SolidityPrimitiveType::Uint(64, false)
+    );
+    assert_eq!(
+        SolidityPrimitiveType::int(256).unwrap(),
+        SolidityPrimitiveType::Int(256, false)
+    );
+    assert_eq!(
+        SolidityPrimitiveType::uint(0).unwrap_err().to_string(),
+        "`uint0` is not a valid ABI type"
+    );
+    assert!(SolidityPrimitiveType::int(13).is_err());
+    assert!(SolidityPrimitiveType::uint(264).is_err());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "invalid primitive type `uint0`")]
+fn invalid_width_head_size() {
+    SolidityType::Primitive(SolidityPrimitiveType::Uint(0, false)).abi_head_size(false);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "invalid primitive type `int13`")]
+fn invalid_width_max_value() {
+    SolidityType::Primitive(SolidityPrimitiveType::Int(13, false)).max_value();
+}
+
+#[test]
+fn packed_layout() {
+    let layout = |sig_str: &str| parse_sig(sig_str).para_types[0].0.packed_layout();
+    assert_eq!(
+        layout("f((bool,uint16,address))"),
+        vec![(0, 1), (1, 2), (3, 20)]
+    );
+    assert_eq!(
+        layout("f((bytes4,uint8[2],int64))"),
+        vec![(0, 4), (4, 64), (68, 8)]
+    );
+    assert_eq!(layout("f((uint8,string,bool))"), vec![(0, 1)]);
+    assert_eq!(layout("f(uint32)"), vec![(0, 4)]);
+}
+
+#[test]
+fn zero_selector() {
+    struct ZeroHasher;
+    impl Keccak256 for ZeroHasher {
+        fn hash(&self, _input: &[u8]) -> [u8; 32] {
+            [0u8; 32]
+        }
+    }
+    let sig = parse_sig("transfer(address,uint256)");
+    assert!(!sig.has_zero_selector());
+    assert!(sig.has_zero_selector_with(&ZeroHasher));
+}
+
+#[test]
+fn mixed_aliases_in_tuple() {
+    let sig = parse_sig("foo((uint,uint256),(int,int256)[])");
+    assert_eq!(sig.to_string(), "foo((uint256,uint256),(int256,int256)[])");
+    assert_eq!(
+        sig.selector_hex(),
+        parse_sig("foo((uint256,uint256),(int256,int256)[])").selector_hex()
+    );
+}
+
+#[test]
+fn has_returns() {
+    assert!(parse_sig("f(uint8) returns (bool)").has_returns());
+    assert!(!parse_sig("f(uint8)").has_returns());
+}
+
+#[test]
+fn glued_data_location() {
+    let sig = parse_sig("f(bytes[]memory,uint8[2]memory,(bool,bytes)memory)");
+    assert_eq!(sig.to_string(), "f(bytes[],uint8[2],(bool,bytes))");
+    assert_eq!(
+        parse_sig_err("f(uint256memory)"),
+        "data location `memory` must be separated from the type in `uint256memory`"
+    );
+    assert_eq!(
+        parse_sig_err("f(bytesmemory)"),
+        "data location `memory` must be separated from the type in `bytesmemory`"
+    );
+}
+
+#[test]
+fn signature_block() {
+    let block = "
+        // token interface
+        transfer(address,uint256) returns (bool)
+
+        balanceOf(address returns (uint256)
+        approve(address,uint256) returns (bool)
+    ";
+    let sigs = parse_signature_block(block);
+    assert_eq!(sigs.len(), 3);
+    assert_eq!(
+        sigs[0].as_ref().unwrap().to_string(),
+        "transfer(address,uint256)"
+    );
+    assert!(sigs[1].is_err());
+    assert_eq!(
+        sigs[2].as_ref().unwrap().to_string(),
+        "approve(address,uint256)"
+    );
+}
+
+#[test]
+fn fingerprint_of_abi() {
+    let sigs = vec![
+        parse_sig("transfer(address,uint256) returns (bool)"),
+        parse_sig("balanceOf(address) returns (uint256)"),
+    ];
+    let reordered = vec![sigs[1].clone(), sigs[0].clone()];
+    assert_eq!(
+        abi_fingerprint(&sigs, &[]),
+        abi_fingerprint(&reordered, &[])
+    );
+    let mut extended = sigs.clone();
+    extended.push(parse_sig("approve(address,uint256) returns (bool)"));
+    assert_ne!(abi_fingerprint(&sigs, &[]), abi_fingerprint(&extended, &[]));
+
+    let events = vec![
+        parse_sig("event Transfer(address indexed,address indexed,uint256)"),
+        parse_sig("event Approval(address indexed,address indexed,uint256)"),
+    ];
+    let fingerprint = abi_fingerprint(&sigs, &events);
+    assert_ne!(fingerprint, abi_fingerprint(&sigs, &[]));
+    assert_eq!(
+        fingerprint,
+        abi_fingerprint(&reordered, &[events[1].clone(), events[0].clone()])
+    );
+    // Adding or changing an event changes the fingerprint
+    let mut more_events = events.clone();
+    more_events.push(parse_sig("event Paused(address)"));
+    assert_ne!(fingerprint, abi_fingerprint(&sigs, &more_events));
+    let changed_events = vec![
+        events[0].clone(),
+        parse_sig("event Approval(address indexed,address indexed,uint128)"),
+    ];
+    assert_ne!(fingerprint, abi_fingerprint(&sigs, &changed_events));
+}
+
+#[test]
+fn bool_array_sizes() {
+    let sig = parse_sig("f(bool[])");
+    let ty = &sig.para_types[0].0;
+    assert!(!ty.is_static());
+    assert_eq!(ty.abi_head_size(true), 32);
+    // The head holds the offset, the tail the length and 3 padded elements.
+    assert_eq!(sig.exact_calldata_size(&[(0, 3)]).unwrap(), 32 + 128);
+}
+
+#[test]
+fn source_spelling_of_aliases() {
+    let sig = parse_sig("send(address payable,(uint,int8)[],uint256)");
+    assert_eq!(
+        sig.to_source_string(),
+        "send(address payable,(uint,int8)[],uint256)"
+    );
+    assert_eq!(sig.to_string(), "send(address,(uint256,int8)[],uint256)");
+    assert_eq!(
+        sig.selector_hex(),
+        parse_sig("send(address,(uint256,int8)[],uint256)").selector_hex()
+    );
+    assert!(sig.shape_eq(&parse_sig("send(address payable,(uint256,int8)[],uint)")));
+}
+
+#[test]
+fn move_representable() {
+    let sig = parse_sig("f(uint64,fixed128x18) returns ((bool,ufixed64x10[]))");
+    assert!(sig.para_types[0].0.is_move_representable());
+    assert!(!sig.para_types[1].0.is_move_representable());
+    assert!(!sig.ret_types[0].0.is_move_representable());
+    assert_eq!(
+        sig.check_move_representable().unwrap_err().to_string(),
+        "parameter type `fixed128x18` has no Move representation"
+    );
+    assert_eq!(
+        parse_sig("f() returns (fixed128x18)")
+            .check_move_representable()
+            .unwrap_err()
+            .to_string(),
+        "return type `fixed128x18` has no Move representation"
+    );
+    assert!(parse_sig("f(uint64,bytes)")
+        .check_move_representable()
+        .is_ok());
+}
+
+#[test]
+fn abi_json_void_outputs() {
+    let json = parse_sig("foo()").to_abi_json();
+    assert_eq!(json["outputs"], serde_json::json!([]));
+    assert!(json.to_string().contains("\"outputs\":[]"));
+}
+
+#[test]
+fn classify_function_role() {
+    let role = |sig_str: &str| parse_sig(sig_str).classify();
+    assert_eq!(
+        role("balanceOf(address) view returns (uint256)"),
+        FunctionRole::Getter
+    );
+    assert_eq!(
+        role("totalSupply() pure returns (uint256)"),
+        FunctionRole::Getter
+    );
+    assert_eq!(role("setOwner(address)"), FunctionRole::Setter);
+    assert_eq!(
+        role("transfer(address,uint256) returns (bool)"),
+        FunctionRole::Action
+    );
+    assert_eq!(role("ping()"), FunctionRole::Action);
+    assert_eq!(role("check(uint8) view"), FunctionRole::Action);
+}
+
+#[test]
+fn param_offset_comments() {
+    assert_eq!(
+        parse_sig("transfer(address,uint256)").offset_comments(),
+        vec!["// param0 (address) at 0x04", "// param1 (uint256) at 0x24"]
+    );
+    assert_eq!(
+        parse_sig("f(bytes,uint8[3],bool)").offset_comments(),
+        vec![
+            "// param0 (bytes) offset-slot at 0x04",
+            "// param1 (uint8[3]) at 0x24",
+            "// param2 (bool) at 0x84"
+        ]
+    );
+}
+
+#[test]
+fn static_bytes_data_location() {
+    // `bytes32` is a value type, `bytes` a reference type
+    assert_eq!(
+        parse_sig_err("f(bytes32 memory)"),
+        "data location can only be specified for array or struct types"
+    );
+    let sig = parse_sig("f(bytes memory,bytes32[] memory)");
+    assert_eq!(sig.to_string(), "f(bytes,bytes32[])");
+}
+
+#[test]
+fn solidity_pragma() {
+    let pragma = parse_sig("transfer(address,uint256) returns (bool)").min_solidity_pragma();
+    assert_eq!(
+        pragma,
+        SolidityPragma {
+            version: ">=0.8.0",
+            needs_abicoder_v2: false
+        }
+    );
+    assert_eq!(pragma.to_string(), "pragma solidity >=0.8.0;");
+    let pragma = parse_sig("f((address,uint256))").min_solidity_pragma();
+    assert!(pragma.needs_abicoder_v2);
+    assert_eq!(
+        pragma.to_string(),
+        "pragma solidity >=0.8.0;\npragma abicoder v2;"
+    );
+}
+
+#[test]
+fn named_structs() {
+    let uint = |n| SolidityType::Primitive(SolidityPrimitiveType::Uint(n, false));
+    let address = SolidityType::Primitive(SolidityPrimitiveType::Address(false));
+    let transfer = vec![address.clone(), uint(256)];
+    let options = SignatureParseOptions {
+        structs: vec![
+            ("Transfer".to_string(), transfer.clone()),
+            (
+                "Memo".to_string(),
+                vec![SolidityType::SolidityString, uint(8)],
+            ),
+            (
+                "Batch".to_string(),
+                vec![
+                    SolidityType::Struct("Transfer".to_string(), transfer, vec![]),
+                    SolidityType::Primitive(SolidityPrimitiveType::Bool),
+                ],
+            ),
+        ]
+        .into_iter()
+        .collect(),
+        ..SignatureParseOptions::default()
+    };
+    let sig = SoliditySignature::parse_with_options(
+        "transfer(Transfer,Memo,Batch[2],(address,uint256))",
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        sig.to_string(),
+        "transfer((address,uint256),(string,uint8),((address,uint256),bool)[2],(address,uint256))"
+    );
+    assert_eq!(
+        sig.to_source_string(),
+        "transfer(Transfer,Memo,Batch[2],(address,uint256))"
+    );
+    assert_eq!(
+        sig.selector_hex(),
+        parse_sig("transfer((address,uint256),(string,uint8),((address,uint256),bool)[2],(address,uint256))")
+            .selector_hex()
+    );
+    let is_static = sig
+        .para_types
+        .iter()
+        .map(|(ty, _)| ty.is_static())
+        .collect::<Vec<_>>();
+    assert_eq!(is_static, vec![true, false, true, true]);
+    assert_eq!(sig.para_types[2].0.abi_head_size(true), 6 * 32);
+    assert_eq!(sig.para_types[0].0.normalize(), sig.para_types[3].0);
+    assert_eq!(
+        sig.referenced_type_names(),
+        vec!["Batch", "Memo", "Transfer"]
+    );
+    assert_eq!(sig.para_types[2].0.abi_internal_type(), "struct Batch[2]");
+    assert_eq!(
+        SoliditySignature::parse_with_options("f(Unknown)", &options)
+            .unwrap_err()
+            .to_string(),
+        "unknown type `Unknown`"
+    );
+}
+
+#[test]
+fn decode_cost_estimates() {
+    let costs = parse_sig("f(uint8,bytes,uint64[4],string)").decode_cost_estimate();
+    let labels = costs
+        .iter()
+        .map(|(label, _)| label.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(labels, vec!["uint8", "bytes", "uint64[4]", "string"]);
+    assert!(costs[1].1 > costs[0].1);
+    assert!(costs[1].1 > costs[2].1);
+    assert!(costs[3].1 > costs[2].1);
+    assert!(costs[2].1 > costs[0].1);
+}
+
+#[test]
+fn signed_integer_compatibility() {
+    let env = GlobalEnv::new();
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    let u64_ty = Type::Primitive(PrimitiveType::U64);
+    let sig = parse_sig("f(int64,uint64)");
+    assert!(!sig.para_types[0].0.check_type_compatibility(&ctx, &u64_ty));
+    assert!(sig.para_types[1].0.check_type_compatibility(&ctx, &u64_ty));
+    assert_eq!(sig.para_types[0].0.suggest_move_type(&ctx), None);
+    assert_eq!(
+        sig.check_move_representable().unwrap_err().to_string(),
+        "parameter type `int64` has no Move representation"
+    );
+    assert!(!parse_sig("f(int8[])").para_types[0]
+        .0
+        .is_move_representable());
+}
+
+#[test]
+fn calldata_location() {
+    let sig = parse_sig("f(bytes calldata,uint64[] calldata,uint8)");
+    assert_eq!(sig.to_string(), "f(bytes,uint64[],uint8)");
+    assert_eq!(sig.para_types[0].1, SignatureDataLocation::CallData);
+    assert_eq!(sig.para_types[1].1, SignatureDataLocation::CallData);
+    assert_eq!(sig.para_types[2].1, SignatureDataLocation::Memory);
+    assert!(!SolidityType::Primitive(SolidityPrimitiveType::Bool)
+        .check_location_compatibility(&SignatureDataLocation::CallData));
+    assert!(SolidityType::Primitive(SolidityPrimitiveType::Bool)
+        .check_location_compatibility(&SignatureDataLocation::Memory));
+    for sig in [
+        "f(uint64 calldata)",
+        "f(bytes32 calldata)",
+        "f(address calldata)",
+    ] {
+        assert_eq!(
+            parse_sig_err(sig),
+            "data location can only be specified for array or struct types"
+        );
+    }
+    assert_eq!(
+        parse_sig_err("f(bytescalldata)"),
+        "data location `calldata` must be separated from the type in `bytescalldata`"
+    );
+}
+
+#[test]
+fn signature_head_sizes() {
+    let sig = parse_sig("f(uint8,bytes,uint64[3],(bool,address)) returns (bytes4,string)");
+    for padded in [true, false] {
+        let params = sig
+            .para_types
+            .iter()
+            .map(|(ty, _)| ty.clone())
+            .collect::<Vec<_>>();
+        let rets = sig
+            .ret_types
+            .iter()
+            .map(|(ty, _)| ty.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sig.params_head_size(padded),
+            params
+                .iter()
+                .map(|ty| ty.abi_head_size(padded))
+                .sum::<usize>()
+        );
+        assert_eq!(
+            sig.returns_head_size(padded),
+            rets.iter()
+                .map(|ty| ty.abi_head_size(padded))
+                .sum::<usize>()
+        );
+    }
+    assert_eq!(sig.params_head_size(true), 32 + 32 + 96 + 64);
+    assert_eq!(sig.returns_head_size(true), 64);
+}
+
+#[test]
+fn nested_tuple_levels() {
+    let sig = parse_sig("foo((uint256,address),bool)");
+    assert_eq!(sig.para_types.len(), 2);
+    assert!(matches!(&sig.para_types[0].0, SolidityType::Tuple(comps) if comps.len() == 2));
+    let sig = parse_sig("foo(((uint8,(bool,address)),bytes32),uint16)");
+    assert_eq!(sig.para_types.len(), 2);
+    assert_eq!(
+        sig.to_string(),
+        "foo(((uint8,(bool,address)),bytes32),uint16)"
+    );
+    assert_eq!(sig.para_types[0].0.depth(), 3);
+    let sig = parse_sig("foo((uint256[],(bool,bytes)),(bool,address)[2]) returns ((uint8,(bool)))");
+    assert_eq!(sig.para_types.len(), 2);
+    match &sig.para_types[0].0 {
+        SolidityType::Tuple(comps) => {
+            assert_eq!(comps[0].to_string(), "uint256[]");
+            assert_eq!(comps[1].to_string(), "(bool,bytes)");
+        }
+        ty => panic!("unexpected type `{}`", ty),
+    }
+    assert_eq!(sig.ret_types[0].0.to_string(), "(uint8,(bool))");
+}
+
+#[test]
+fn abi_json_nested_components() {
+    let sig = parse_sig("f((uint8,(bool,address payable)[])[2]) returns (bytes)");
+    assert_eq!(
+        sig.to_abi_json()["inputs"],
+        serde_json::json!([{
+            "name": "",
+            "type": "tuple[2]",
+            "internalType": "tuple[2]",
+            "components": [
+                {"name": "", "type": "uint8", "internalType": "uint8"},
+                {
+                    "name": "",
+                    "type": "tuple[]",
+                    "internalType": "tuple[]",
+                    "components": [
+                        {"name": "", "type": "bool", "internalType": "bool"},
+                        {"name": "", "type": "address", "internalType": "address payable"},
+                    ],
+                },
+            ],
+        }])
+    );
+    assert_eq!(
+        SoliditySignature::from_abi_json(&sig.to_abi_json())
+            .unwrap()
+            .to_string(),
+        sig.to_string()
+    );
+}
+
+#[test]
+fn validates_utf8() {
+    let sig = parse_sig("f() returns (string,bytes,bytes32)");
+    let flags = sig
+        .ret_types
+        .iter()
+        .map(|(ty, _)| ty.validates_utf8())
+        .collect::<Vec<_>>();
+    assert_eq!(flags, vec![true, false, false]);
+}
+
+#[test]
+fn jagged_dynamic_array() {
+    let u256 = SolidityType::Primitive(SolidityPrimitiveType::uint(256).unwrap());
+    let ty = parse_sig("f(uint256[][])").para_types[0].0.clone();
+    assert_eq!(
+        ty,
+        SolidityType::DynamicArray(Box::new(SolidityType::DynamicArray(Box::new(u256))))
+    );
+    assert!(!ty.is_static());
+    assert_eq!(ty.abi_head_size(true), 32);
+
+    let env = GlobalEnv::new();
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    let u64_vector = Type::Vector(Box::new(Type::Primitive(PrimitiveType::U64)));
+    let jagged = parse_sig("f(uint64[][])").para_types[0].0.clone();
+    assert!(jagged.check_type_compatibility(&ctx, &Type::Vector(Box::new(u64_vector.clone()))));
+    assert!(!jagged.check_type_compatibility(&ctx, &u64_vector));
+    let bool_vector = Type::Vector(Box::new(Type::Primitive(PrimitiveType::Bool)));
+    assert!(!jagged.check_type_compatibility(&ctx, &Type::Vector(Box::new(bool_vector))));
+}
+
+#[test]
+fn selector_bytes_and_hex() {
+    let sig = parse_sig("transfer(address, uint) returns (bool)");
+    assert_eq!(sig.selector(), [0xa9, 0x05, 0x9c, 0xbb]);
+    assert_eq!(sig.selector_hex(), "0xa9059cbb");
+    assert_eq!(sig.selector_string(), "0xa9059cbb");
+    // Aliases inside tuples and arrays are normalized before hashing
+    assert_eq!(
+        parse_sig("f((uint,int8)[2][],uint[])").selector(),
+        selector_of("f((uint256,int8)[2][],uint256[])")
+    );
+}
+
+#[test]
+fn static_bytes_head_size() {
+    assert_eq!(SolidityType::BytesStatic(4).abi_head_size(false), 4);
+    assert_eq!(SolidityType::BytesStatic(4).abi_head_size(true), 32);
+    assert_eq!(SolidityType::BytesStatic(32).abi_head_size(false), 32);
+    let sig = parse_sig("f(bytes4,bytes1[3])");
+    assert_eq!(sig.params_head_size(false), 4 + 3);
+}
+
+#[test]
+fn collides_with_standard() {
+    for (selector, sig) in KNOWN_SELECTORS {
+        assert_eq!(selector_of(sig), *selector, "{}", sig);
+        assert_eq!(parse_sig(sig).collides_with_standard(), None);
+    }
+    // Both share the selector 0xa9059cbb
+    assert_eq!(
+        parse_sig("func_2093253501(bytes)").collides_with_standard(),
+        Some("transfer(address,uint256)")
+    );
+    assert_eq!(
+        parse_sig("transfer(address,uint128)").collides_with_standard(),
+        None
+    );
+}
+
+#[test]
+fn explicit_signature() {
+    let sig = parse_sig("f(uint, int[2], (uint8, address payable)[], fixed) returns (uint)");
+    assert_eq!(
+        sig.explicit_signature(),
+        "f(uint256,int256[2],(uint8,address)[],fixed128x18)"
+    );
+    assert_eq!(sig.explicit_signature(), sig.to_string());
+    assert_eq!(selector_of(&sig.explicit_signature()), sig.selector());
+}
+
+#[test]
+fn multi_dimensional_array_compatibility() {
+    let env = GlobalEnv::new();
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    let vector = |ty: Type| Type::Vector(Box::new(ty));
+    let u64_ty = Type::Primitive(PrimitiveType::U64);
+    let three_dims = vector(vector(vector(u64_ty.clone())));
+    for sig in [
+        "f(uint64[][][])",
+        "f(uint64[][2][])",
+        "f(uint64[3][][4])",
+        "f(uint8[3][2][1])",
+    ] {
+        let ty = &parse_sig(sig).para_types[0].0;
+        assert!(ty.check_type_compatibility(&ctx, &three_dims), "{}", sig);
+        // Wrong nesting depth in either direction
+        assert!(
+            !ty.check_type_compatibility(&ctx, &vector(vector(u64_ty.clone()))),
+            "{}",
+            sig
+        );
+        assert!(
+            !ty.check_type_compatibility(&ctx, &vector(three_dims.clone())),
+            "{}",
+            sig
+        );
+    }
+    assert_eq!(
+        SolidityType::try_translate_from_move(&ctx, &three_dims)
+            .unwrap()
+            .to_string(),
+        "uint64[][][]"
+    );
+}
+
+#[test]
+fn solidity_type_from_str() {
+    let ty = "uint256[]".parse::<SolidityType>().unwrap();
+    assert_eq!(ty.to_string(), "uint256[]");
+    assert_eq!(
+        " (bool,bytes4)[2] ".parse::<SolidityType>().unwrap(),
+        parse_sig("f((bool,bytes4)[2])").para_types[0].0
+    );
+    assert_eq!("uint7".parse::<SolidityType>().unwrap_err().ty_str, "uint7");
+    let err: SolidityTypeParseError = "a-b".parse::<SolidityType>().unwrap_err();
+    assert_eq!(err.to_string(), "illegal type name `a-b`");
+}
+
+#[test]
+fn exposed_internal_fields() {
+    // A mocked struct `Foo { owner: address, secret: u64, nonce: u64 }` flagging `secret`
+    let fields = vec![
+        "owner".to_string(),
+        "secret".to_string(),
+        "nonce".to_string(),
+    ];
+    let internal = vec!["secret".to_string()].into_iter().collect();
+    assert_eq!(
+        exposed_field_warnings("Foo", &fields, &internal),
+        vec!["struct `Foo` exposes field `secret` in the ABI"]
+    );
+    assert!(exposed_field_warnings("Foo", &fields, &Default::default()).is_empty());
+}
+
+#[test]
+fn validate_calldata_len() {
+    let sig = parse_sig("f(uint256,bytes)");
+    assert_eq!(sig.min_calldata_size(), 4 + 64 + 32);
+    assert_eq!(
+        sig.validate_calldata_len(99).unwrap_err(),
+        "calldata of 99 bytes is shorter than the minimum of 100 bytes for `f(uint256,bytes)`"
+    );
+    assert!(sig.validate_calldata_len(100).is_ok());
+    assert!(sig.validate_calldata_len(132).is_ok());
+    assert_eq!(parse_sig("f()").min_calldata_size(), 4);
+    assert_eq!(
+        parse_sig("f(bool,uint8[3])").min_calldata_size(),
+        4 + 32 * 4
+    );
+    // Offsets and length words of the nested dynamic values
+    assert_eq!(
+        parse_sig("f((uint8,string),bytes[2])").min_calldata_size(),
+        4 + (32 + 64 + 32) + (32 + 2 * 64)
+    );
+}
+
+#[test]
+fn structured_type_errors() {
+    use SolidityTypeError::*;
+    let kind = |ty_str: &str| ty_str.parse::<SolidityType>().unwrap_err().kind;
+    assert_eq!(kind("uint9"), IntRange("uint9".to_string()));
+    assert_eq!(kind("int0"), IntRange("int0".to_string()));
+    assert_eq!(kind("uintX"), UnknownType("uintX".to_string()));
+    assert_eq!(kind("fixed255x15"), FixedRange("fixed255x15".to_string()));
+    assert_eq!(kind("ufixed64X18"), UnknownType("ufixed64X18".to_string()));
+    assert_eq!(kind("bytes33"), BytesRange("bytes33".to_string()));
+    assert_eq!(
+        kind("address pyable"),
+        UnknownType("address pyable".to_string())
+    );
+    assert_eq!(kind("uint8[x]"), BadArrayLength("uint8[x]".to_string()));
+    assert_eq!(kind("uint8[2"), UnbalancedBrackets("uint8[2".to_string()));
+    assert_eq!(kind("(bool,)"), MalformedTuple("(bool,)".to_string()));
+    assert_eq!(kind("()"), EmptyTuple("()".to_string()));
+    // The offending part of a nested type is reported
+    assert_eq!(kind("(bool,bytes0)[]"), BytesRange("bytes0".to_string()));
+    // Errors remain distinguishable through signature parsing
+    let err = SoliditySignature::parse_into_solidity_signature("f(uint8[x])").unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<SolidityTypeError>(),
+        Some(&BadArrayLength("uint8[x]".to_string()))
+    );
+    assert_eq!(err.to_string(), "invalid array length in `uint8[x]`");
+}
+
+#[test]
+fn parse_error_spans() {
+    fn span(sig_str: &str) -> (&str, String) {
+        let err = SoliditySignature::parse_into_solidity_signature(sig_str).unwrap_err();
+        let span = err.downcast_ref::<SignatureErrorSpan>().unwrap();
+        (
+            &sig_str[span.offset..span.offset + span.len],
+            err.to_string(),
+        )
+    }
+    assert_eq!(
+        span("transfer(address, uintX)"),
+        ("uintX", "unknown type `uintX`".to_string())
+    );
+    assert_eq!(
+        span("  f(bool) returns (bool, (uint8,bytes0)[] memory)"),
+        (
+            "bytes0",
+            "the size of `bytes0` must be between 1 and 32".to_string()
+        )
+    );
+    assert_eq!(
+        span("f(uint8, uint64 memory)"),
+        (
+            "uint64 memory",
+            "data location can only be specified for array or struct types".to_string()
+        )
+    );
+    let err = SoliditySignature::parse_into_solidity_signature("f(uint64,)").unwrap_err();
+    let empty = err.downcast_ref::<SignatureErrorSpan>().unwrap();
+    assert_eq!((empty.offset, empty.len), (9, 0));
+    // Errors which don't concern a single parameter have no span
+    let err = SoliditySignature::parse_into_solidity_signature("f() returns uint64").unwrap_err();
+    assert!(err.downcast_ref::<SignatureErrorSpan>().is_none());
+}
+
+#[test]
+fn annotated_signature() {
+    let options = SignatureParseOptions {
+        structs: vec![(
+            "Transfer".to_string(),
+            vec![
+                SolidityType::Primitive(SolidityPrimitiveType::Address(false)),
+                SolidityType::Primitive(SolidityPrimitiveType::Uint(256, false)),
+            ],
+        )]
+        .into_iter()
+        .collect(),
+        struct_field_names: vec![(
+            "Transfer".to_string(),
+            vec!["to".to_string(), "amount".to_string()],
+        )]
+        .into_iter()
+        .collect(),
+        ..SignatureParseOptions::default()
+    };
+    let sig = SoliditySignature::parse_with_options("transfer(Transfer)", &options).unwrap();
+    assert_eq!(
+        sig.annotated_signature(),
+        "transfer((address to, uint256 amount))"
+    );
+    assert_eq!(sig.to_string(), "transfer((address,uint256))");
+    // Structs whose field names are unknown are rendered as plain tuples
+    let sig = SoliditySignature::parse_into_solidity_signature("f((address,bool)[])").unwrap();
+    assert_eq!(sig.annotated_signature(), "f((address, bool)[])");
+}
+
+#[test]
+fn packed_differs_from_standard() {
+    let differs = |ty_str: &str| {
+        ty_str
+            .parse::<SolidityType>()
+            .unwrap()
+            .packed_differs_from_standard()
+    };
+    assert!(!differs("uint256"));
+    assert!(differs("uint8"));
+    assert!(differs("address"));
+    assert!(differs("bool"));
+    assert!(differs("bytes4"));
+    assert!(!differs("bytes32"));
+    assert!(differs("bytes"));
+    assert!(differs("uint256[]"));
+    // Elements of static arrays are padded in both modes
+    assert!(!differs("uint8[3]"));
+    assert!(!differs("(uint256,int256)"));
+    assert!(differs("(uint256,bool)"));
+}
+
+#[test]
+fn packed_size() {
+    let ty: SolidityType = "(bool,uint16,bytes3)".parse().unwrap();
+    assert_eq!(ty.packed_size(), Some(1 + 2 + 3));
+    assert_eq!(ty.abi_head_size(true), 3 * 32);
+    let ty: SolidityType = "(address,(uint64,bool)[2])".parse().unwrap();
+    assert_eq!(ty.packed_size(), Some(20 + 2 * 9));
+    assert_eq!(ty.abi_head_size(true), 5 * 32);
+    // Dynamic types are inlined without their length, so their packed size is not fixed
+    assert_eq!(
+        "(bool,bytes)"
+            .parse::<SolidityType>()
+            .unwrap()
+            .packed_size(),
+        None
+    );
+    assert_eq!(
+        "uint8[]".parse::<SolidityType>().unwrap().packed_size(),
+        None
+    );
+}
+
+#[test]
+fn event_and_error_signatures() {
+    let address = || SolidityType::Primitive(SolidityPrimitiveType::Address(false));
+    let event = SoliditySignatureBuilder::new()
+        .name("Transfer")
+        .kind(SignatureKind::Event)
+        .param(address(), SignatureDataLocation::Memory)
+        .param(address(), SignatureDataLocation::Memory)
+        .param(
+            SolidityType::Primitive(SolidityPrimitiveType::Uint(256, false)),
+            SignatureDataLocation::Memory,
+        )
+        .build()
+        .unwrap();
+    assert_eq!(
+        hex::encode(event.identifier()),
+        "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+    );
+    let json = event.to_abi_json();
+    assert_eq!(json["type"], "event");
+    assert!(json.get("stateMutability").is_none());
+    assert!(SoliditySignature::from_abi_json(&json)
+        .unwrap()
+        .abi_equivalent(&event));
+
+    let error = SoliditySignatureBuilder::new()
+        .name("Error")
+        .kind(SignatureKind::Error)
+        .param(SolidityType::SolidityString, SignatureDataLocation::Memory)
+        .build()
+        .unwrap();
+    assert_eq!(hex::encode(error.identifier()), "08c379a0");
+    assert_eq!(error.to_abi_json()["type"], "error");
+    assert!(!error.abi_equivalent(&parse_sig("Error(string)")));
+    assert_eq!(parse_sig("Error(string)").identifier(), error.identifier());
+}
+
+#[test]
+fn payable_address_compatibility() {
+    let env = GlobalEnv::new();
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    let address = Type::Primitive(PrimitiveType::Address);
+    let signer = Type::Primitive(PrimitiveType::Signer);
+    for payable in [false, true] {
+        let ty = SolidityType::Primitive(SolidityPrimitiveType::Address(payable));
+        assert!(ty.check_type_compatibility(&ctx, &address));
+        assert!(ty.check_type_compatibility(&ctx, &signer));
+        assert!(!ty.check_type_compatibility(&ctx, &Type::Primitive(PrimitiveType::U64)));
+    }
+    let sig = parse_sig("f(address,address payable)");
+    assert!(sig.para_types[0].0.check_type_compatibility(&ctx, &address));
+    assert!(sig.para_types[1].0.check_type_compatibility(&ctx, &address));
+}
+
+#[test]
+fn params_head_words() {
+    assert_eq!(parse_sig("f()").params_head_words(), 0);
+    assert_eq!(parse_sig("f(uint8,bool,bytes)").params_head_words(), 3);
+    // A static tuple is inlined, a dynamic one is referenced by an offset
+    assert_eq!(
+        parse_sig("f((uint256,address,bool),uint64)").params_head_words(),
+        4
+    );
+    assert_eq!(
+        parse_sig("f((uint256,string),uint64)").params_head_words(),
+        2
+    );
+    assert_eq!(parse_sig("f(uint8[3])").params_head_words(), 3);
+}
+
+#[test]
+fn indexed_event_params() {
+    let sig = parse_sig("event Transfer(address indexed, address indexed, uint256)");
+    assert_eq!(sig.kind, SignatureKind::Event);
+    assert_eq!(sig.indexed_params, vec![0, 1].into_iter().collect());
+    assert_eq!(sig.to_string(), "Transfer(address,address,uint256)");
+    let inputs = sig.to_abi_json()["inputs"].clone();
+    assert_eq!(inputs[0]["indexed"], true);
+    assert_eq!(inputs[2]["indexed"], false);
+    assert!(abi_json_round_trip(&sig).abi_equivalent(&sig));
+    assert_eq!(
+        parse_sig("error Unauthorized(address)").kind,
+        SignatureKind::Error
+    );
+
+    assert_eq!(
+        parse_sig_err("event E(uint8 indexed, bool indexed, bytes32 indexed, address indexed)"),
+        "an event can have at most 3 indexed parameters"
+    );
+    assert_eq!(
+        parse_sig_err("f(address indexed)"),
+        "`indexed` is only allowed for event parameters"
+    );
+    assert_eq!(
+        parse_sig_err("event E(uint8) returns (bool)"),
+        "state mutability and return types are only allowed for functions"
+    );
+    assert!(SoliditySignatureBuilder::new()
+        .name("f")
+        .indexed_param(SolidityType::Bytes)
+        .build()
+        .is_err());
+}
+
+#[test]
+fn named_params() {
+    let sig = parse_sig("transfer(address to, uint256 amount) returns (bool success)");
+    assert_eq!(sig.to_string(), "transfer(address,uint256)");
+    assert_eq!(sig.para_names.get(&0).map(String::as_str), Some("to"));
+    assert_eq!(sig.para_names.get(&1).map(String::as_str), Some("amount"));
+    assert_eq!(sig.ret_names.get(&0).map(String::as_str), Some("success"));
+    let json = sig.to_abi_json();
+    assert_eq!(json["inputs"][0]["name"], "to");
+    assert_eq!(json["outputs"][0]["name"], "success");
+    assert_eq!(abi_json_round_trip(&sig).para_names, sig.para_names);
+
+    // Keywords following the type are not names
+    let sig = parse_sig("f(address payable, bytes memory data, uint8[] calldata, uint8)");
+    assert_eq!(sig.para_types[0].0.to_source_string(), "address payable");
+    assert_eq!(sig.para_types[1].1, SignatureDataLocation::Memory);
+    assert_eq!(sig.para_types[2].1, SignatureDataLocation::CallData);
+    assert_eq!(
+        sig.para_names.into_iter().collect::<Vec<_>>(),
+        vec![(1, "data".to_string())]
+    );
+    let sig = parse_sig("event Transfer(address indexed from, address indexed to, uint256 value)");
+    assert_eq!(sig.indexed_params, vec![0, 1].into_iter().collect());
+    assert_eq!(sig.para_names.len(), 3);
+    assert_eq!(parse_sig("f(address payable to)").para_names[&0], "to");
+    // A type is not a name
+    assert_eq!(parse_sig_err("f(uint8 bool)"), "unknown type `uint8 bool`");
+}
+
+#[test]
+fn identifier_edge_cases() {
+    assert_eq!(parse_sig("_transfer(address)").sig_name, "_transfer");
+    assert_eq!(parse_sig("$burn(uint256)").sig_name, "$burn");
+    assert_eq!(parse_sig("__init__()").sig_name, "__init__");
+    assert_eq!(
+        parse_sig_err("2foo()"),
+        "function name cannot start with a digit"
+    );
+}
+
+#[test]
+fn selector_from_parts_matches_signature() {
+    let sig = parse_sig("f(uint,(address,bytes)[2],string)");
+    let params = sig
+        .para_types
+        .iter()
+        .map(|(ty, _)| ty.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(selector_from_parts("f", &params), sig.selector());
+    let params = vec![
+        SolidityType::Primitive(SolidityPrimitiveType::Address(false)),
+        SolidityType::Primitive(SolidityPrimitiveType::Uint(256, false)),
+    ];
+    assert_eq!(
+        selector_from_parts("transfer", &params),
+        [0xa9, 0x05, 0x9c, 0xbb]
+    );
+}
+
+#[test]
+fn strict_strings_policy() {
+    let env = GlobalEnv::new();
+    let byte_vector = Type::Vector(Box::new(Type::Primitive(PrimitiveType::U8)));
+    let string_array: SolidityType = "string[]".parse().unwrap();
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    assert!(
+        string_array.check_type_compatibility(&ctx, &Type::Vector(Box::new(byte_vector.clone())))
+    );
+    assert_eq!(
+        SolidityType::SolidityString.suggest_move_type(&ctx),
+        Some(byte_vector.clone())
+    );
+    let options = Options {
+        strict_strings: true,
+        ..Options::default()
+    };
+    let ctx = Context::new(&options, &env, false);
+    // Nested strings are rejected as well, and no byte vector is suggested for them
+    assert!(
+        !string_array.check_type_compatibility(&ctx, &Type::Vector(Box::new(byte_vector.clone())))
+    );
+    assert_eq!(SolidityType::SolidityString.suggest_move_type(&ctx), None);
+    assert!("bytes[]"
+        .parse::<SolidityType>()
+        .unwrap()
+        .check_type_compatibility(&ctx, &Type::Vector(Box::new(byte_vector))));
+}
+
+#[test]
+fn unsubstituted_type_parameter() {
+    let env = GlobalEnv::new();
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    let err = |ty: Type| {
+        SolidityType::try_translate_from_move(&ctx, &ty)
+            .unwrap_err()
+            .to_string()
+    };
+    let expected = "unsubstituted type parameter in ABI type; generic functions must be \
+                    instantiated before ABI export";
+    assert_eq!(err(Type::TypeParameter(0)), expected);
+    assert_eq!(
+        err(Type::Vector(Box::new(Type::TypeParameter(1)))),
+        expected
+    );
+}
+
+#[test]
+fn user_defined_value_types() {
+    let env = GlobalEnv::new();
+    let options = Options {
+        value_types: vec!["Price=uint256".to_string()],
+        ..Options::default()
+    };
+    let ctx = Context::new(&options, &env, false);
+    let sig = SoliditySignature::parse_with_options(
+        "setPrice(Price,Price[])",
+        ctx.signature_parse_options(),
+    )
+    .unwrap();
+    assert_eq!(sig.to_string(), "setPrice(uint256,uint256[])");
+    assert_eq!(
+        sig.selector(),
+        parse_sig("setPrice(uint256,uint256[])").selector()
+    );
+    assert_eq!(sig.params_head_size(true), 64);
+    let inputs = sig.to_abi_json()["inputs"].clone();
+    assert_eq!(inputs[0]["type"], "uint256");
+    assert_eq!(inputs[0]["internalType"], "Price");
+    assert_eq!(inputs[1]["internalType"], "Price[]");
+    // Unknown value types are still unknown
+    assert_eq!(parse_sig_err("setPrice(Price)"), "unknown type `Price`");
+
+    assert!(parse_value_type("Flag = bool").is_ok());
+    assert!(parse_value_type("Price").is_err());
+    assert!(parse_value_type("uint8=uint256").is_err());
+    assert_eq!(
+        parse_value_type("Name=string").unwrap_err().to_string(),
+        "the underlying type of value type `Name` must be a primitive type, found `string`"
+    );
+}
+
+#[test]
+fn calldata_template() {
+    let template = parse_sig("transfer(address,uint256)").calldata_template();
+    assert!(template.starts_with("0xa9059cbb"));
+    let slots = &template["0xa9059cbb".len()..];
+    assert_eq!(slots, "XX".repeat(64));
+    let template = parse_sig("f((uint8,bool),bytes)").calldata_template();
+    assert_eq!(
+        &template[10..],
+        format!("{}{}", "XX".repeat(64), "??".repeat(32))
+    );
+    assert_eq!(parse_sig("f()").calldata_template().len(), 10);
+}
+
+#[test]
+fn tail_size_for_len() {
+    let tail = |ty_str: &str, len| {
+        ty_str
+            .parse::<SolidityType>()
+            .unwrap()
+            .tail_size_for_len(len)
+    };
+    assert_eq!(tail("bytes", 0), 32);
+    assert_eq!(tail("bytes", 31), 64);
+    assert_eq!(tail("bytes", 32), 64);
+    assert_eq!(tail("bytes", 33), 96);
+    assert_eq!(tail("string", 5), 64);
+    assert_eq!(tail("uint64[]", 3), 32 + 3 * 32);
+    assert_eq!(tail("(uint8,bool)[]", 2), 32 + 2 * 64);
+    // Only the offsets of dynamic elements are included
+    assert_eq!(tail("bytes[]", 2), 32 + 2 * 32);
+    assert_eq!(tail("uint256", 7), 0);
+}
+
+#[test]
+fn serde_round_trip() {
+    let sig = parse_sig(
+        "swap((address,uint)[] calldata path, bytes memory data, fixed128x18, int8[3]) view \
+         returns (bytes32[2], string)",
+    );
+    let json = serde_json::to_string(&sig).unwrap();
+    assert!(json.contains(r#""(address,uint256)[]","calldata""#));
+    assert!(json.contains(r#""mutability":"view""#));
+    let deserialized: SoliditySignature = serde_json::from_str(&json).unwrap();
+    assert!(deserialized.abi_equivalent(&sig));
+    // Aliases like `uint` are serialized in their canonical form
+    let normalized = |sig: &SoliditySignature| {
+        sig.para_types
+            .iter()
+            .map(|(ty, loc)| (ty.normalize(), loc.clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(normalized(&deserialized), normalized(&sig));
+    assert_eq!(deserialized.para_names, sig.para_names);
+    assert_eq!(deserialized.to_string(), sig.to_string());
+    // Signatures without names of structs or user-defined value types and without
+    // `address payable` round-trip exactly
+    let sig = parse_sig(
+        "swap((address,uint256)[] calldata path, bytes memory data, fixed128x18, int8[3]) view \
+         returns (bytes32[2], string)",
+    );
+    let deserialized: SoliditySignature =
+        serde_json::from_str(&serde_json::to_string(&sig).unwrap()).unwrap();
+    assert_eq!(deserialized, sig);
+    let sig = parse_sig("pay(address payable)");
+    let deserialized: SoliditySignature =
+        serde_json::from_str(&serde_json::to_string(&sig).unwrap()).unwrap();
+    assert_ne!(deserialized, sig);
+    assert_eq!(deserialized.selector(), sig.selector());
+
+    let ty: SolidityType = serde_json::from_str(r#""uint8[][2]""#).unwrap();
+    assert_eq!(serde_json::to_string(&ty).unwrap(), r#""uint8[][2]""#);
+    assert!(serde_json::from_str::<SolidityType>(r#""uint7""#).is_err());
+    assert!(serde_json::from_str::<SolidityPrimitiveType>(r#""bytes""#).is_err());
+}
+
+#[test]
+fn reference_compatibility() {
+    let env = GlobalEnv::new();
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    let u64_ref = Type::Reference(false, Box::new(Type::Primitive(PrimitiveType::U64)));
+    let sig = parse_sig("f(uint64,uint128,int64)");
+    assert!(sig.para_types[0].0.check_type_compatibility(&ctx, &u64_ref));
+    assert!(!sig.para_types[1].0.check_type_compatibility(&ctx, &u64_ref));
+    assert!(!sig.para_types[2].0.check_type_compatibility(&ctx, &u64_ref));
+    let vec_ref = Type::Reference(
+        true,
+        Box::new(Type::Vector(Box::new(Type::Primitive(PrimitiveType::U8)))),
+    );
+    assert!(parse_sig("f(bytes)").para_types[0]
+        .0
+        .check_type_compatibility(&ctx, &vec_ref));
+}
+
+#[test]
+fn returns_stack_limit() {
+    let sig = parse_sig("f() returns (uint256[8],(uint8,bool)[4],address)");
+    assert!(sig.returns_exceed_stack_limit());
+    let sig = parse_sig("f() returns (uint256[8],(uint8,bool)[4])");
+    assert!(!sig.returns_exceed_stack_limit());
+    // Dynamic return values only take one slot for their memory pointer
+    let sig = parse_sig("f(uint256[16]) returns (bytes,string,uint64[],uint8)");
+    assert!(!sig.returns_exceed_stack_limit());
+    assert!(sig.exceeds_stack_limit());
+}
+
+#[test]
+fn display_parse_round_trip() {
+    use SolidityPrimitiveType::*;
+    let mut leaves = vec![
+        SolidityType::SolidityString,
+        SolidityType::Bytes,
+        SolidityType::BytesStatic(1),
+        SolidityType::BytesStatic(32),
+    ];
+    leaves.extend(
+        [
+            Bool,
+            Address(false),
+            Uint(8, false),
+            Uint(256, false),
+            Int(64, false),
+            Fixed(128, 18),
+            Ufixed(8, 1),
+            Fixed(256, 80),
+        ]
+        .iter()
+        .map(|p| SolidityType::Primitive(*p)),
+    );
+    // Grow the set of types by wrapping all types of the previous generation in arrays and
+    // combining them into tuples
+    let mut generation = leaves.clone();
+    let mut all = leaves.clone();
+    for _ in 0..2 {
+        let mut next = vec![];
+        for (i, ty) in generation.iter().enumerate() {
+            next.push(SolidityType::DynamicArray(Box::new(ty.clone())));
+            next.push(SolidityType::StaticArray(Box::new(ty.clone()), i + 1));
+            next.push(SolidityType::Tuple(vec![ty.clone()]));
+            next.push(SolidityType::Tuple(vec![
+                ty.clone(),
+                leaves[i % leaves.len()].clone(),
+                generation[(i * 7 + 3) % generation.len()].clone(),
+            ]));
+        }
+        all.extend(next.iter().cloned());
+        generation = next;
+    }
+    for ty in all {
+        let printed = ty.to_string();
+        let parsed: SolidityType = printed.parse().unwrap();
+        assert_eq!(parsed, ty, "`{}` does not round-trip", printed);
+    }
+
+    // The spelling of integer aliases is not compared
+    for ty in [Uint(256, true), Int(256, true)] {
+        let ty = SolidityType::Primitive(ty);
+        assert_eq!(ty.to_string().parse::<SolidityType>().unwrap(), ty);
+    }
+
+    // Variants which carry source information only round-trip to their canonical form
+    let lossy = vec![
+        SolidityType::Primitive(Address(true)),
+        SolidityType::UserDefined("Price".to_string(), Ufixed(128, 18)),
+        SolidityType::DynamicArray(Box::new(SolidityType::Struct(
+            "Pair".to_string(),
+            vec![
+                SolidityType::Primitive(Address(true)),
+                SolidityType::Primitive(Uint(256, true)),
+            ],
+            vec!["owner".to_string(), "amount".to_string()],
+        ))),
+    ];
+    for ty in lossy {
+        let parsed: SolidityType = ty.to_string().parse().unwrap();
+        assert_ne!(parsed, ty);
+        assert_eq!(parsed, ty.normalize());
+    }
+}
+
+#[test]
+fn payable_address_returns() {
+    let sig = parse_sig("owner() view returns (address payable)");
+    assert!(sig.ret_types[0].0.is_payable_address());
+    assert_eq!(sig.to_string(), "owner()");
+    assert_eq!(sig.ret_types[0].0.to_string(), "address");
+    assert!(!parse_sig("owner() view returns (address)").ret_types[0]
+        .0
+        .is_payable_address());
+
+    let sig = parse_sig(
+        "f() returns (address payable[] memory, (address payable,uint8)[2], address payable[3])",
+    );
+    match &sig.ret_types[0].0 {
+        SolidityType::DynamicArray(ety) => assert!(ety.is_payable_address()),
+        ty => panic!("unexpected type {:?}", ty),
+    }
+    match &sig.ret_types[1].0 {
+        SolidityType::StaticArray(ety, 2) => match &**ety {
+            SolidityType::Tuple(tys) => {
+                assert!(tys[0].is_payable_address());
+                assert!(!tys[1].is_payable_address());
+            }
+            ty => panic!("unexpected type {:?}", ty),
+        },
+        ty => panic!("unexpected type {:?}", ty),
+    }
+    assert_eq!(
+        sig.ret_types
+            .iter()
+            .map(|(ty, _)| ty.to_string())
+            .collect::<Vec<_>>(),
+        vec!["address[]", "(address,uint8)[2]", "address[3]"]
+    );
+    assert!(!sig.ret_types[0].0.is_payable_address());
+}
+
+#[test]
+fn module_abi_report_of_callables() {
+    let env = model_from_source(
+        r#"
+#[contract]
+module 0x2::M {
+    #[callable]
+    fun implicit(x: u64): u128 { (x as u128) }
+
+    #[callable(sig=b"add(uint64,uint64) returns (uint64)")]
+    fun add(x: u64, y: u64): u64 { x + y }
+
+    #[callable(sig=b"mismatch(uint128)")]
+    fun mismatch(_x: u64) {}
+
+    #[callable(sig=b"signed(int64)")]
+    fun signed(_x: u64) {}
+
+    #[callable(sig=b"broken(uint9)")]
+    fun broken(_x: u64) {}
+
+    fun internal(): u64 { 0 }
+}
+"#,
+    );
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    let report = module_abi_report(&ctx, &ctx.get_target_functions(|_| true));
+    assert!(!env.has_errors());
+    let reports = report
+        .callables
+        .iter()
+        .map(|callable| (callable.function.as_str(), callable))
+        .collect::<BTreeMap<_, _>>();
+    assert_eq!(
+        reports.keys().cloned().collect::<Vec<_>>(),
+        vec!["add", "broken", "implicit", "mismatch", "signed"]
+    );
+
+    let implicit = reports["implicit"];
+    assert_eq!(implicit.signature.as_deref(), Some("implicit(uint64)"));
+    assert_eq!(
+        implicit.selector,
+        Some(parse_sig("implicit(uint64)").selector_hex())
+    );
+    assert!(implicit.compatible);
+    assert_eq!(implicit.explanation, None);
+
+    let add = reports["add"];
+    assert_eq!(add.signature.as_deref(), Some("add(uint64,uint64)"));
+    assert!(add.compatible);
+
+    let mismatch = reports["mismatch"];
+    assert_eq!(mismatch.signature.as_deref(), Some("mismatch(uint128)"));
+    assert!(mismatch.selector.is_some());
+    assert!(!mismatch.compatible);
+    assert_eq!(
+        mismatch.explanation.as_deref(),
+        Some("solidity signature is not compatible with the move signature")
+    );
+
+    let signed = reports["signed"];
+    assert!(!signed.compatible);
+    assert_eq!(
+        signed.explanation.as_deref(),
+        Some("parameter type `int64` has no Move representation")
+    );
+
+    let broken = reports["broken"];
+    assert_eq!(broken.signature, None);
+    assert_eq!(broken.selector, None);
+    assert!(!broken.compatible);
+    assert!(broken.explanation.as_ref().unwrap().contains("uint9"));
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert_eq!(
+        serde_json::from_str::<ModuleAbiReport>(&json).unwrap(),
+        report
+    );
+}
+
+#[test]
+fn single_returns_clause() {
+    assert_eq!(
+        parse_sig_err("f() returns (uint256) returns (bool)"),
+        "only one `returns` clause is allowed"
+    );
+    assert_eq!(
+        parse_sig_err("f() returns (uint256)returns(bool)"),
+        "only one `returns` clause is allowed"
+    );
+    assert_eq!(
+        parse_sig_err("f() returns (uint256) returnsX"),
+        "unexpected trailing tokens after signature: `returnsX`"
+    );
+    assert_eq!(
+        parse_sig_err("f() returns (uint256) ; junk"),
+        "unexpected trailing tokens after signature: `; junk`"
+    );
+    // Whitespace after the returns clause or the parameters is not trailing content
+    assert_eq!(parse_sig("f() returns (uint256) \t ").ret_types.len(), 1);
+    assert!(parse_sig("f(uint8)   ").ret_types.is_empty());
+}
+
+#[test]
+fn overload_suffix() {
+    assert_eq!(strip_overload_suffix("transfer_2"), "transfer");
+    assert_eq!(strip_overload_suffix("transfer_from_10"), "transfer_from");
+    assert_eq!(strip_overload_suffix("transfer_from"), "transfer_from");
+    assert_eq!(strip_overload_suffix("transfer_"), "transfer_");
+    assert_eq!(strip_overload_suffix("transfer2"), "transfer2");
+    assert_eq!(strip_overload_suffix("_2"), "_2");
+}
+
+#[test]
+fn overloads_by_suffix() {
+    let env = model_from_source(
+        r#"
+#[contract]
+module 0x2::M {
+    #[callable]
+    fun transfer_1(_to: address) {}
+
+    #[callable]
+    fun transfer_2(_to: address, _amount: u64) {}
+}
+"#,
+    );
+    let options = Options {
+        overload_suffixes: true,
+        ..Options::default()
+    };
+    let (_, out) = Generator::run(&options, &env);
+    assert!(!env.has_errors(), "{}", diagnostics(&env));
+    for sig in ["transfer(address)", "transfer(address,uint64)"] {
+        let selector = parse_sig(sig).selector_hex();
+        assert!(out.contains(&format!("case {}", selector)), "{}", sig);
+    }
+}
+
+#[test]
+fn overloads_by_suffix_collide() {
+    let env = model_from_source(
+        r#"
+#[contract]
+module 0x2::M {
+    #[callable]
+    fun transfer_1(_to: address) {}
+
+    #[callable]
+    fun transfer_2(_from: address) {}
+}
+"#,
+    );
+    let options = Options {
+        overload_suffixes: true,
+        ..Options::default()
+    };
+    let (_, out) = Generator::run(&options, &env);
+    assert!(env.has_errors());
+    assert!(diagnostics(&env)
+        .contains("signature `transfer(address)` is the same as that of `M::transfer_1`"));
+    // Only the first of the colliding functions is dispatched
+    let case = format!("case {}", parse_sig("transfer(address)").selector_hex());
+    assert_eq!(out.matches(&case).count(), 1);
+}
+
+#[test]
+fn generic_callable() {
+    let env = model_from_source(
+        r#"
+#[contract]
+module 0x2::M {
+    #[callable]
+    fun id<T: drop>(x: T): T { x }
+}
+"#,
+    );
+    let (_, out) = Generator::run(&Options::default(), &env);
+    assert!(env.has_errors());
+    assert!(diagnostics(&env).contains(
+        "unsubstituted type parameter in ABI type; generic functions must be instantiated \
+         before ABI export"
+    ));
+    // The function is not dispatched
+    assert!(!out.contains("case 0x"));
+}
+
+#[test]
+fn structs_with_same_name() {
+    let env = model_from_source(
+        r#"
+module 0x2::M {
+    struct S has drop { a: u64 }
+}
+
+module 0x2::N {
+    struct S has drop { a: u64, b: bool }
+    struct T has drop { s: S }
+}
+"#,
+    );
+    let options = Options::default();
+    let ctx = Context::new(&options, &env, false);
+    let parse =
+        |sig: &str| SoliditySignature::parse_with_options(sig, ctx.signature_parse_options());
+    assert_eq!(
+        parse("f(M::S,N::S)").unwrap().selector_hex(),
+        parse_sig("f((uint64),(uint64,bool))").selector_hex()
+    );
+    // The bare name is ambiguous, unless it is unique among the target modules
+    assert!(parse("f(S)").is_err());
+    assert_eq!(
+        parse("f(T)").unwrap().selector_hex(),
+        parse_sig("f(((uint64,bool)))").selector_hex()
+    );
+    // Qualified and unique bare names are compatible with the Move struct they refer to
+    let struct_ty = |module: &str, name: &str| {
+        let module_env = env
+            .find_module_by_name(env.symbol_pool().make(module))
+            .unwrap();
+        let struct_env = module_env
+            .find_struct(env.symbol_pool().make(name))
+            .unwrap();
+        Type::Struct(module_env.get_id(), struct_env.get_id(), vec![])
+    };
+    let is_compatible = |sig: &str, move_ty: &Type| {
+        parse(sig).unwrap().para_types[0]
+            .0
+            .check_type_compatibility(&ctx, move_ty)
+    };
+    assert!(is_compatible("f(M::S)", &struct_ty("M", "S")));
+    assert!(is_compatible("f(N::S)", &struct_ty("N", "S")));
+    assert!(!is_compatible("f(M::S)", &struct_ty("N", "S")));
+    assert!(is_compatible("f(T)", &struct_ty("N", "T")));
+    assert!(is_compatible("f(N::T)", &struct_ty("N", "T")));
+}
+
+#[test]
+fn max_type_depth_while_parsing() {
+    // Nesting far beyond the limit is rejected without deep recursion
+    let tuple = format!("{}uint8{}", "(".repeat(10_000), ")".repeat(10_000));
+    let array = format!("uint8{}", "[]".repeat(10_000));
+    for ty in [&tuple, &array] {
+        assert!(matches!(
+            ty.parse::<SolidityType>().unwrap_err().kind,
+            SolidityTypeError::TooDeep(_, 32)
+        ));
+        let sig = format!("f({})", ty);
+        assert!(parse_sig_err(&sig).ends_with("exceeds the maximum nesting depth of 32"));
+    }
+    let at_limit = format!("uint8{}", "[]".repeat(32));
+    assert_eq!(at_limit.parse::<SolidityType>().unwrap().depth(), 32);
+    let over_limit = format!("({})", at_limit);
+    assert_eq!(
+        over_limit.parse::<SolidityType>().unwrap_err().to_string(),
+        format!(
+            "type `{}` exceeds the maximum nesting depth of 32",
+            over_limit
+        )
+    );
+
+    let options = SignatureParseOptions {
+        max_type_depth: 2,
+        ..SignatureParseOptions::default()
+    };
+    assert!(SoliditySignature::parse_with_options("f((uint8[],bool))", &options).is_ok());
+    assert_eq!(
+        SoliditySignature::parse_with_options("f((uint8[][],bool))", &options)
+            .unwrap_err()
+            .to_string(),
+        "type `(uint8[][],bool)` exceeds the maximum nesting depth of 2"
+    );
+}
+
+#[test]
+fn integer_alias_equality() {
+    use std::collections::{hash_map::DefaultHasher, BTreeSet};
+    use std::hash::{Hash, Hasher};
+    let hash = |ty: &SolidityType| {
+        let mut hasher = DefaultHasher::new();
+        ty.hash(&mut hasher);
+        hasher.finish()
+    };
+    let sig = parse_sig("f(uint,uint256,int,int256,uint128)");
+    let tys = sig.para_types.iter().map(|(ty, _)| ty).collect::<Vec<_>>();
+    assert_eq!(tys[0], tys[1]);
+    assert_eq!(tys[2], tys[3]);
+    assert_ne!(tys[0], tys[2]);
+    assert_ne!(tys[0], tys[4]);
+    assert_eq!(hash(tys[0]), hash(tys[1]));
+    assert_eq!(tys.iter().collect::<BTreeSet<_>>().len(), 3);
+    // The spelling is still available for diagnostics
+    assert_eq!(tys[0].to_source_string(), "uint");
+    assert_eq!(tys[1].to_source_string(), "uint256");
+    assert!(parse_sig("f(uint[2])").shape_eq(&parse_sig("f(uint256[2])")));
+}
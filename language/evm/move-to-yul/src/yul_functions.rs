@@ -49,6 +49,74 @@ static PLACEHOLDERS: Lazy<BTreeMap<&'static str, &'static str>> = Lazy::new(|| {
         "MAX_U256" =>
         "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
 
+        // Signed maximum values
+        "MAX_I8" => "0x7f",
+        "MAX_I16" => "0x7fff",
+        "MAX_I24" => "0x7fffff",
+        "MAX_I32" => "0x7fffffff",
+        "MAX_I40" => "0x7fffffffff",
+        "MAX_I48" => "0x7fffffffffff",
+        "MAX_I56" => "0x7fffffffffffff",
+        "MAX_I64" => "0x7fffffffffffffff",
+        "MAX_I72" => "0x7fffffffffffffffff",
+        "MAX_I80" => "0x7fffffffffffffffffff",
+        "MAX_I88" => "0x7fffffffffffffffffffff",
+        "MAX_I96" => "0x7fffffffffffffffffffffff",
+        "MAX_I104" => "0x7fffffffffffffffffffffffff",
+        "MAX_I112" => "0x7fffffffffffffffffffffffffff",
+        "MAX_I120" => "0x7fffffffffffffffffffffffffffff",
+        "MAX_I128" => "0x7fffffffffffffffffffffffffffffff",
+        "MAX_I136" => "0x7fffffffffffffffffffffffffffffffff",
+        "MAX_I144" => "0x7fffffffffffffffffffffffffffffffffff",
+        "MAX_I152" => "0x7fffffffffffffffffffffffffffffffffffff",
+        "MAX_I160" => "0x7fffffffffffffffffffffffffffffffffffffff",
+        "MAX_I168" => "0x7fffffffffffffffffffffffffffffffffffffffff",
+        "MAX_I176" => "0x7fffffffffffffffffffffffffffffffffffffffffff",
+        "MAX_I184" => "0x7fffffffffffffffffffffffffffffffffffffffffffff",
+        "MAX_I192" => "0x7fffffffffffffffffffffffffffffffffffffffffffffff",
+        "MAX_I200" => "0x7fffffffffffffffffffffffffffffffffffffffffffffffff",
+        "MAX_I208" => "0x7fffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "MAX_I216" => "0x7fffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "MAX_I224" => "0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "MAX_I232" => "0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "MAX_I240" => "0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "MAX_I248" => "0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "MAX_I256" => "0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+
+        // Signed minimum values in two's complement representation
+        "MIN_I8" => "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff80",
+        "MIN_I16" => "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff8000",
+        "MIN_I24" => "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffff800000",
+        "MIN_I32" => "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffff80000000",
+        "MIN_I40" => "0xffffffffffffffffffffffffffffffffffffffffffffffffffffff8000000000",
+        "MIN_I48" => "0xffffffffffffffffffffffffffffffffffffffffffffffffffff800000000000",
+        "MIN_I56" => "0xffffffffffffffffffffffffffffffffffffffffffffffffff80000000000000",
+        "MIN_I64" => "0xffffffffffffffffffffffffffffffffffffffffffffffff8000000000000000",
+        "MIN_I72" => "0xffffffffffffffffffffffffffffffffffffffffffffff800000000000000000",
+        "MIN_I80" => "0xffffffffffffffffffffffffffffffffffffffffffff80000000000000000000",
+        "MIN_I88" => "0xffffffffffffffffffffffffffffffffffffffffff8000000000000000000000",
+        "MIN_I96" => "0xffffffffffffffffffffffffffffffffffffffff800000000000000000000000",
+        "MIN_I104" => "0xffffffffffffffffffffffffffffffffffffff80000000000000000000000000",
+        "MIN_I112" => "0xffffffffffffffffffffffffffffffffffff8000000000000000000000000000",
+        "MIN_I120" => "0xffffffffffffffffffffffffffffffffff800000000000000000000000000000",
+        "MIN_I128" => "0xffffffffffffffffffffffffffffffff80000000000000000000000000000000",
+        "MIN_I136" => "0xffffffffffffffffffffffffffffff8000000000000000000000000000000000",
+        "MIN_I144" => "0xffffffffffffffffffffffffffff800000000000000000000000000000000000",
+        "MIN_I152" => "0xffffffffffffffffffffffffff80000000000000000000000000000000000000",
+        "MIN_I160" => "0xffffffffffffffffffffffff8000000000000000000000000000000000000000",
+        "MIN_I168" => "0xffffffffffffffffffffff800000000000000000000000000000000000000000",
+        "MIN_I176" => "0xffffffffffffffffffff80000000000000000000000000000000000000000000",
+        "MIN_I184" => "0xffffffffffffffffff8000000000000000000000000000000000000000000000",
+        "MIN_I192" => "0xffffffffffffffff800000000000000000000000000000000000000000000000",
+        "MIN_I200" => "0xffffffffffffff80000000000000000000000000000000000000000000000000",
+        "MIN_I208" => "0xffffffffffff8000000000000000000000000000000000000000000000000000",
+        "MIN_I216" => "0xffffffffff800000000000000000000000000000000000000000000000000000",
+        "MIN_I224" => "0xffffffff80000000000000000000000000000000000000000000000000000000",
+        "MIN_I232" => "0xffffff8000000000000000000000000000000000000000000000000000000000",
+        "MIN_I240" => "0xffff800000000000000000000000000000000000000000000000000000000000",
+        "MIN_I248" => "0xff80000000000000000000000000000000000000000000000000000000000000",
+        "MIN_I256" => "0x8000000000000000000000000000000000000000000000000000000000000000",
+
         // ---------------------------------
         // Memory
         // The size of the memory used by the compilation scheme. This must be the
@@ -12,14 +12,14 @@ module 0x2::M {
     }
 
     // One parameter and one return value
-    #[callable(sig=b"$_fun1$   (uint64)returns (int64)")]
+    #[callable(sig=b"$_fun1$   (uint64)returns (uint64)")]
     fun fun_1(x: u64) : u64 {
         x
     }
 
     // Multiple parameter and multiple return values
-    // Compatibility between integer / unsigned integer types in Move and Solidity
-    #[callable(sig=b"add( int192,uint32 ) returns (int256, int24)")]
+    // Compatibility between unsigned integer types of different widths in Move and Solidity
+    #[callable(sig=b"add( uint192,uint32 ) returns (uint256, uint24)")]
     fun f1(x: U256, y: u64): (U256, u64) {
         (x, y)
     }
@@ -31,7 +31,7 @@ module 0x2::M {
     }
 
     // Compatibility between vector in Move and array, bytes and string in Solidity
-    #[callable(sig=b"fun_2(int120[ 3 ][][5] memory , address payable [],bytes   [2]memory, bytes1 ,bytes32 ) returns (uint64)")]
+    #[callable(sig=b"fun_2(uint120[ 3 ][][5] memory , address payable [],bytes   [2]memory, bytes1 ,bytes32 ) returns (uint64)")]
     fun f3(_vec0: vector<vector<vector<u128>>>, _vec1: vector<address>, _vec2: vector<vector<u8>>, _vec3: vector<u8>, _vec4: vector<u8>): u128 {
         2
     }
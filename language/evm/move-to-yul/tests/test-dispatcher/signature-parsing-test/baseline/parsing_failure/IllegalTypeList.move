@@ -83,7 +83,7 @@ module 0x2::M {
         0
     }
 
-    #[callable(sig=b"f(address pyable) returns (uint64) ")]
+    #[callable(sig=b"f(address pyable x) returns (uint64) ")]
     fun illegal_address_1(_x: address) : u64 {
         0
     }